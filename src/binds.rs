@@ -0,0 +1,325 @@
+//! Key bindings - which keys trigger which player actions.
+//!
+//! Each local player has their own table so splitscreen players can rebind independently.
+//! In singleplayer, all the tables control the same player.
+//!
+//! Binds are changed by the `bind <key> <action>` and `unbind <key>` commands
+//! (`bind2`, `unbind2` etc. for the other players), `unbind all` clears the whole table.
+//! They're saved by `writeconfig` like cvars.
+//!
+//! Key names are macroquad's `KeyCode` names (case insensitive), e.g. `LeftShift` or `kp0`.
+//! Some keys are reported differently depending on keyboard layout
+//! (https://github.com/not-fl3/macroquad/issues/260) - the bind list (F1)
+//! shows the name of the last pressed key so you can bind whatever your layout reports.
+
+use std::fmt::{self, Display, Formatter};
+
+use macroquad::input::KeyCode;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::game_state::Input;
+
+/// Each local player has their own binds, gamepad and viewport.
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+
+/// What a key does when held - one for each `Input` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    TurretLeft,
+    TurretRight,
+    PrevWeapon,
+    NextWeapon,
+    Fire,
+    Mine,
+    SelfDestruct,
+    Horn,
+    Chat,
+    Pause,
+}
+
+impl Action {
+    fn apply(self, input: &mut Input) {
+        let field = match self {
+            Action::Left => &mut input.left,
+            Action::Right => &mut input.right,
+            Action::Up => &mut input.up,
+            Action::Down => &mut input.down,
+            Action::TurretLeft => &mut input.turret_left,
+            Action::TurretRight => &mut input.turret_right,
+            Action::PrevWeapon => &mut input.prev_weapon,
+            Action::NextWeapon => &mut input.next_weapon,
+            Action::Fire => &mut input.fire,
+            Action::Mine => &mut input.mine,
+            Action::SelfDestruct => &mut input.self_destruct,
+            Action::Horn => &mut input.horn,
+            Action::Chat => &mut input.chat,
+            Action::Pause => &mut input.pause,
+        };
+        *field = true;
+    }
+}
+
+/// Keys of one player in the order they were bound.
+///
+/// A key does one action, an action can have any number of keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Binds(Vec<(KeyCode, Action)>);
+
+// Keys to avoid in defaults:
+//  - Ctrl - ctrl+W closes the browser tab
+//  - Alt - shows/hides the firefox menu bar on linux
+//  - Numpad - Some keyboards might not have it
+//  - Keys that often depend on layout - https://github.com/not-fl3/macroquad/issues/260
+
+impl Binds {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn new_player1() -> Self {
+        Self(vec![
+            (KeyCode::A, Action::Left),
+            (KeyCode::D, Action::Right),
+            (KeyCode::W, Action::Up),
+            (KeyCode::S, Action::Down),
+            (KeyCode::Q, Action::TurretLeft),
+            (KeyCode::E, Action::TurretRight),
+            (KeyCode::V, Action::PrevWeapon),
+            (KeyCode::LeftShift, Action::NextWeapon),
+            (KeyCode::C, Action::NextWeapon),
+            (KeyCode::Space, Action::Fire),
+            (KeyCode::X, Action::Mine),
+            (KeyCode::G, Action::SelfDestruct),
+            (KeyCode::R, Action::Horn),
+            // Shared actions like chat and pause are only bound for player 1 by default.
+            (KeyCode::Enter, Action::Chat),
+            (KeyCode::T, Action::Chat),
+            (KeyCode::Pause, Action::Pause),
+            (KeyCode::P, Action::Pause),
+        ])
+    }
+
+    pub fn new_player2() -> Self {
+        Self(vec![
+            (KeyCode::Left, Action::Left),
+            (KeyCode::Right, Action::Right),
+            (KeyCode::Up, Action::Up),
+            (KeyCode::Down, Action::Down),
+            (KeyCode::Comma, Action::TurretLeft),
+            (KeyCode::Period, Action::TurretRight),
+            (KeyCode::L, Action::PrevWeapon),
+            (KeyCode::Slash, Action::NextWeapon), // US layout
+            (KeyCode::Minus, Action::NextWeapon), // Same key, CZ layout
+            (KeyCode::Kp0, Action::NextWeapon),
+            (KeyCode::RightShift, Action::Fire),
+            (KeyCode::M, Action::Mine),
+            (KeyCode::J, Action::SelfDestruct),
+            (KeyCode::K, Action::Horn),
+        ])
+    }
+
+    /// Default binds indexed by player.
+    ///
+    /// There aren't enough keys for more than 2 players, the rest need gamepads or custom binds.
+    pub fn new_players() -> [Self; MAX_LOCAL_PLAYERS] {
+        [
+            Self::new_player1(),
+            Self::new_player2(),
+            Self::new(),
+            Self::new(),
+        ]
+    }
+
+    /// Make the key do the action, replacing what it did before.
+    pub fn bind(&mut self, key: KeyCode, action: Action) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(bind) => bind.1 = action,
+            None => self.0.push((key, action)),
+        }
+    }
+
+    /// Returns whether the key was bound.
+    pub fn unbind(&mut self, key: KeyCode) -> bool {
+        let len = self.0.len();
+        self.0.retain(|&(k, _)| k != key);
+        self.0.len() != len
+    }
+
+    pub fn unbind_all(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyCode> {
+        self.0
+            .iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|&(key, _)| key)
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (KeyCode, Action)> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Input with the actions of all the keys which are active.
+    pub fn input(&self, is_active: impl Fn(KeyCode) -> bool) -> Input {
+        let mut input = Input::new();
+        for &(key, action) in &self.0 {
+            if is_active(key) {
+                action.apply(&mut input);
+            }
+        }
+        input
+    }
+
+    /// Parse `<key> <action>` or `<key>=<action>`, the latter works in the console
+    /// which doesn't allow spaces in values.
+    pub fn bind_str(&mut self, str_value: &str) -> Result<(), String> {
+        let (key, action) = str_value
+            .split_once(|c: char| c == '=' || c.is_whitespace())
+            .ok_or_else(|| format!("expected <key> <action>, found {str_value}"))?;
+        let key = parse_key(key.trim())?;
+        let action = parse_action(action.trim())?;
+        self.bind(key, action);
+        Ok(())
+    }
+}
+
+/// Bound keys of each action, e.g. `left: A, up: W`.
+impl Display for Binds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for action in Action::iter() {
+            let keys = self.keys(action);
+            if keys.is_empty() {
+                continue;
+            }
+            if !first {
+                write!(f, ", ")?;
+            }
+            first = false;
+            let names: Vec<_> = keys.into_iter().map(key_name).collect();
+            write!(f, "{}: {}", action, names.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_action(name: &str) -> Result<Action, String> {
+    name.parse().map_err(|_| {
+        let actions: Vec<_> = Action::iter().map(|action| action.to_string()).collect();
+        format!(
+            "unknown action {name}, expected one of: {}",
+            actions.join(", ")
+        )
+    })
+}
+
+pub fn parse_key(name: &str) -> Result<KeyCode, String> {
+    KEYS.iter()
+        .copied()
+        .find(|&key| key_name(key).eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown key {name}"))
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// All keys which can be bound.
+///
+/// Macroquad doesn't provide a way to iterate or parse them.
+#[rustfmt::skip]
+const KEYS: &[KeyCode] = &[
+    KeyCode::Space, KeyCode::Apostrophe, KeyCode::Comma, KeyCode::Minus, KeyCode::Period,
+    KeyCode::Slash, KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Semicolon,
+    KeyCode::Equal, KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F,
+    KeyCode::G, KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M,
+    KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T,
+    KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::LeftBracket, KeyCode::Backslash, KeyCode::RightBracket, KeyCode::GraveAccent,
+    KeyCode::World1, KeyCode::World2, KeyCode::Escape, KeyCode::Enter, KeyCode::Tab,
+    KeyCode::Backspace, KeyCode::Insert, KeyCode::Delete, KeyCode::Right, KeyCode::Left,
+    KeyCode::Down, KeyCode::Up, KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End,
+    KeyCode::CapsLock, KeyCode::ScrollLock, KeyCode::NumLock, KeyCode::PrintScreen,
+    KeyCode::Pause, KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::F13, KeyCode::F14, KeyCode::F15, KeyCode::F16, KeyCode::F17, KeyCode::F18,
+    KeyCode::F19, KeyCode::F20, KeyCode::F21, KeyCode::F22, KeyCode::F23, KeyCode::F24,
+    KeyCode::F25, KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2, KeyCode::Kp3, KeyCode::Kp4,
+    KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9, KeyCode::KpDecimal,
+    KeyCode::KpDivide, KeyCode::KpMultiply, KeyCode::KpSubtract, KeyCode::KpAdd,
+    KeyCode::KpEnter, KeyCode::KpEqual, KeyCode::LeftShift, KeyCode::LeftControl,
+    KeyCode::LeftAlt, KeyCode::LeftSuper, KeyCode::RightShift, KeyCode::RightControl,
+    KeyCode::RightAlt, KeyCode::RightSuper, KeyCode::Menu,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binds() {
+        let mut binds = Binds::new_player2();
+        assert_eq!(binds.action(KeyCode::Minus), Some(Action::NextWeapon));
+        assert_eq!(
+            binds.keys(Action::NextWeapon),
+            vec![KeyCode::Slash, KeyCode::Minus, KeyCode::Kp0]
+        );
+
+        binds.bind_str("kp0 fire").unwrap();
+        binds.bind_str("W=up").unwrap();
+        binds.bind_str("leftshift  turret_left").unwrap();
+        assert_eq!(binds.action(KeyCode::Kp0), Some(Action::Fire));
+        assert_eq!(binds.action(KeyCode::W), Some(Action::Up));
+        assert_eq!(binds.action(KeyCode::LeftShift), Some(Action::TurretLeft));
+        assert!(binds.bind_str("w").is_err());
+        assert!(binds.bind_str("nope up").is_err());
+        let err = binds.bind_str("w jump").unwrap_err();
+        assert!(err.contains("self_destruct"), "{err}");
+
+        assert!(binds.unbind(KeyCode::Up));
+        assert!(!binds.unbind(KeyCode::Up));
+
+        let input = binds.input(|key| key == KeyCode::W || key == KeyCode::Slash);
+        assert!(input.up && input.next_weapon);
+        assert!(!input.fire && !input.down);
+
+        let text = binds.to_string();
+        assert!(
+            text.starts_with("left: Left, right: Right, up: W, "),
+            "{text}"
+        );
+        assert!(
+            text.contains("next_weapon: Slash Minus, fire: Kp0 RightShift"),
+            "{text}"
+        );
+
+        binds.unbind_all();
+        assert_eq!(binds, Binds::new());
+        assert_eq!(binds.to_string(), "");
+    }
+
+    #[test]
+    fn test_key_names() {
+        for &key in KEYS {
+            assert_eq!(parse_key(&key_name(key)), Ok(key));
+        }
+        assert_eq!(parse_key("GRAVEACCENT"), Ok(KeyCode::GraveAccent));
+        assert!(parse_key("Unknown").is_err());
+    }
+}
@@ -0,0 +1,540 @@
+//! Config files - lists of cvars to set, one `name value` pair per line.
+//!
+//! Lines starting with `#` are comments. Config files can run other config files
+//! using `exec <path>` (or `include <path>`) so shared settings can live in one file.
+//! Relative paths in a config file are relative to that file's directory.
+//!
+//! The same commands can be used in the console and on the command line,
+//! `writeconfig <path>` saves all cvars which differ from the defaults,
+//! `balance <preset>` switches between RecWars and the original RecWar gameplay.
+//!
+//! Cheat-protected cvars (see `CvarFlags`) can only be changed while `sv_cheats` is enabled.
+//!
+//! Key bindings are set by `bind <key> <action>` and friends, see `binds`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cvars::SetGet;
+use strum::IntoEnumIterator;
+
+use crate::{
+    binds::{self, MAX_LOCAL_PLAYERS},
+    cvars::{Balance, CvarFlags, Cvars},
+};
+
+/// Executed at startup (before cvars from the command line) if it exists.
+pub const AUTOEXEC_PATH: &str = "autoexec.cfg";
+
+/// Suggested path for `writeconfig`.
+pub const DEFAULT_CONFIG_PATH: &str = "config.cfg";
+
+/// Run a config file.
+///
+/// Invalid lines don't stop the rest of the file from running,
+/// all the problems are reported together afterwards.
+pub fn exec(cvars: &mut Cvars, path: &str) -> Result<(), String> {
+    let mut stack = Vec::new();
+    let errors = exec_file(cvars, Path::new(path), &mut stack);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn exec_file(cvars: &mut Cvars, path: &Path, stack: &mut Vec<PathBuf>) -> Vec<String> {
+    // Compare canonical paths so the same file reached through different relative paths is detected.
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    if stack.contains(&canonical) {
+        return vec![format!("{}: recursive exec", path.display())];
+    }
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => return vec![format!("failed to read {}: {}", path.display(), err)],
+    };
+
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let errors = exec_lines(cvars, &text, dir, stack)
+        .into_iter()
+        .map(|(line, err)| format!("{}:{}: {}", path.display(), line, err))
+        .collect();
+    stack.pop();
+    errors
+}
+
+/// Returns line numbers (1-based) and error messages.
+fn exec_lines(
+    cvars: &mut Cvars,
+    text: &str,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Vec<(usize, String)> {
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // The value is the rest of the line so it can contain spaces (e.g. map names).
+        let (name, value) = match line.split_once(char::is_whitespace) {
+            Some((name, value)) => (name, value.trim()),
+            None => (line, ""),
+        };
+        match name {
+            "exec" | "include" => {
+                for err in exec_file(cvars, &dir.join(value), stack) {
+                    errors.push((i + 1, err));
+                }
+            }
+            _ if bind_player(name).is_some() => {
+                if let Err(err) = bind_command(cvars, name, value) {
+                    errors.push((i + 1, err));
+                }
+            }
+            _ if value.is_empty() => errors.push((i + 1, format!("missing value for {name}"))),
+            _ => {
+                if let Err(err) = set_protected(cvars, name, value) {
+                    errors.push((i + 1, err));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Set a cvar unless it's cheat protected and cheats are disabled.
+pub fn set_protected(cvars: &mut Cvars, cvar_name: &str, str_value: &str) -> Result<(), String> {
+    // Check the cvar exists first so typos don't get reported as cheats.
+    cvars.get_value(cvar_name)?;
+    if !cvars.sv_cheats && CvarFlags::of(cvar_name).cheat {
+        return Err(format!(
+            "{cvar_name} is cheat protected, set sv_cheats true first"
+        ));
+    }
+    cvars.set_value(cvar_name, str_value)
+}
+
+/// Which player's binds a bind command changes, `None` if it's not a bind command.
+///
+/// `bind` and `unbind` are for player 1, `bind2`, `unbind2` etc. for the others.
+fn bind_player(command: &str) -> Option<usize> {
+    let suffix = command
+        .strip_prefix("unbind")
+        .or_else(|| command.strip_prefix("bind"))?;
+    match suffix {
+        "" => Some(0),
+        "1" => None,
+        _ => suffix
+            .parse::<usize>()
+            .ok()
+            .filter(|&player| (2..=MAX_LOCAL_PLAYERS).contains(&player))
+            .map(|player| player - 1),
+    }
+}
+
+/// Run `bind <key> <action>` or `unbind <key>` (`unbind all` removes all binds of the player).
+///
+/// Binds are client settings so they're never cheat protected.
+fn bind_command(cvars: &mut Cvars, command: &str, value: &str) -> Result<(), String> {
+    let binds = &mut cvars.cl_binds[bind_player(command).unwrap()];
+    if command.starts_with("unbind") {
+        if value == "all" {
+            binds.unbind_all();
+        } else if !binds.unbind(binds::parse_key(value)?) {
+            return Err(format!("{value} is not bound"));
+        }
+        Ok(())
+    } else {
+        binds.bind_str(value)
+    }
+}
+
+/// All cvars formatted as `name value [flags]`, one per line.
+pub fn cvar_list(cvars: &Cvars) -> Vec<String> {
+    Cvars::names()
+        .into_iter()
+        .map(|name| {
+            let value = cvars.get_value(&name).unwrap();
+            format!("{name} {value} [{}]", CvarFlags::of(&name))
+        })
+        .collect()
+}
+
+/// Config file text with all cvars which differ from the defaults.
+pub fn write_config(cvars: &Cvars) -> String {
+    let defaults = Cvars::default();
+    let mut text = "# Cvars which differ from the defaults, generated by writeconfig\n".to_owned();
+    for name in Cvars::names() {
+        // A zero seed is replaced by a random one at startup,
+        // saving that would make every game the same.
+        if name == "d_seed" {
+            continue;
+        }
+        let value = cvars.get_value(&name).unwrap();
+        if value != defaults.get_value(&name).unwrap() {
+            text.push_str(&format!("{name} {value}\n"));
+        }
+    }
+    // Replace the whole table so removed default binds stay removed.
+    for (i, (binds, default)) in cvars.cl_binds.iter().zip(&defaults.cl_binds).enumerate() {
+        if binds != default {
+            let suffix = if i == 0 {
+                String::new()
+            } else {
+                (i + 1).to_string()
+            };
+            text.push_str(&format!("unbind{suffix} all\n"));
+            for (key, action) in binds.iter() {
+                let key = binds::key_name(key);
+                text.push_str(&format!("bind{suffix} {key} {action}\n"));
+            }
+        }
+    }
+    text
+}
+
+/// Cvars which differ between `old` and `new` formatted as `name old -> new`.
+pub fn changed_cvars(old: &Cvars, new: &Cvars) -> Vec<String> {
+    Cvars::names()
+        .into_iter()
+        .filter_map(|name| {
+            let old_value = old.get_value(&name).unwrap();
+            let new_value = new.get_value(&name).unwrap();
+            if old_value == new_value {
+                None
+            } else {
+                Some(format!("{name} {old_value} -> {new_value}"))
+            }
+        })
+        .collect()
+}
+
+/// Reset gameplay cvars to a balance preset and return what changed.
+///
+/// Client cvars (see `CvarFlags`) and the map rotation are not part of the balance and are kept.
+pub fn load_balance(cvars: &mut Cvars, balance: Balance) -> Vec<String> {
+    let old = cvars.clone();
+    cvars.load_balance(balance);
+    // The seed is randomized at startup, resetting it would make every game the same.
+    cvars.d_seed = old.d_seed;
+    // Binds are personal preferences, not gameplay.
+    cvars.cl_binds = old.cl_binds.clone();
+    cvars.sv_map_rotation = old.sv_map_rotation.clone();
+    for name in Cvars::names() {
+        if CvarFlags::of(&name).client {
+            let value = old.get_value(&name).unwrap();
+            cvars.set_value(&name, &value).unwrap();
+        }
+    }
+    changed_cvars(&old, cvars)
+}
+
+/// Cvars plus config commands so they can be used from the console
+/// (which only understands `name` and `name value`).
+#[derive(Debug)]
+pub struct Commands<'a>(pub &'a mut Cvars);
+
+impl SetGet for Commands<'_> {
+    fn get_string(&self, cvar_name: &str) -> Result<String, String> {
+        match cvar_name {
+            "exec" | "include" => Err(format!("usage: {cvar_name} <path>")),
+            // Saving only happens through `set_str` so looking at a command never writes files.
+            "writeconfig" => Err(format!(
+                "usage: writeconfig <path>, e.g. writeconfig {DEFAULT_CONFIG_PATH}"
+            )),
+            _ if cvar_name.starts_with("bind") && bind_player(cvar_name).is_some() => {
+                let binds = &self.0.cl_binds[bind_player(cvar_name).unwrap()];
+                Ok(format!(
+                    "usage: {cvar_name} <key>=<action>, current binds: {binds}"
+                ))
+            }
+            _ if bind_player(cvar_name).is_some() => {
+                Err(format!("usage: {cvar_name} <key> or {cvar_name} all"))
+            }
+            "balance" => {
+                // Describe the presets since the console doesn't show output of setting a value.
+                let presets: Vec<_> = Balance::iter()
+                    .map(|balance| {
+                        let changes = load_balance(&mut Cvars::default(), balance);
+                        format!(
+                            "{balance} ({} changes: {})",
+                            changes.len(),
+                            changes.join(", ")
+                        )
+                    })
+                    .collect();
+                Ok(format!(
+                    "usage: balance <preset>, presets: {}",
+                    presets.join("; ")
+                ))
+            }
+            _ => {
+                let value = self.0.get_value(cvar_name)?;
+                Ok(format!("{value} [{}]", CvarFlags::of(cvar_name)))
+            }
+        }
+    }
+
+    fn set_str(&mut self, cvar_name: &str, str_value: &str) -> Result<(), String> {
+        match cvar_name {
+            "exec" | "include" => exec(self.0, str_value),
+            "writeconfig" => write_config_file(self.0, str_value),
+            _ if bind_player(cvar_name).is_some() => bind_command(self.0, cvar_name, str_value),
+            "balance" => {
+                // Presets change gameplay cvars so they're protected the same way.
+                if !self.0.sv_cheats {
+                    return Err("balance is cheat protected, set sv_cheats true first".to_owned());
+                }
+                let balance = str_value
+                    .parse()
+                    .map_err(|_| format!("unknown balance preset {str_value}"))?;
+                let changes = load_balance(self.0, balance);
+                dbg_logf!("Balance {}, changed {} cvars:", balance, changes.len());
+                for change in changes {
+                    dbg_logf!("    {}", change);
+                }
+                Ok(())
+            }
+            _ => set_protected(self.0, cvar_name, str_value),
+        }
+    }
+}
+
+fn write_config_file(cvars: &Cvars, path: &str) -> Result<(), String> {
+    // LATER This doesn't work on the web.
+    fs::write(path, write_config(cvars)).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use crate::{cvars::MapList, entities::Weapon};
+
+    #[test]
+    fn test_cvar_names() {
+        // Every name is a cvar which can be set to its own value.
+        let mut cvars = Cvars::default();
+        let names = Cvars::names();
+        for name in &names {
+            let value = cvars.get_value(name).unwrap();
+            cvars.set_value(name, &value).unwrap();
+        }
+        // In declaration order, skipped fields are only included if `get_value` handles them.
+        assert_eq!(names[0], "ai");
+        assert!(names.contains(&"sv_map_rotation".to_owned()));
+        assert!(names.contains(&"g_rockets.speed".to_owned()));
+        assert!(!names.contains(&"cl_binds".to_owned()));
+        assert!(!names.contains(&"g_weapons".to_owned()));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut cvars = Cvars::default();
+        cvars.set_value("g_rockets.speed", "700").unwrap();
+        cvars.set_value("g_tank.bfg.hardpoint", "Chassis").unwrap();
+        let written = write_config(&cvars);
+        assert!(written.contains("\ng_rockets.speed 700\n"), "{written}");
+        assert!(
+            written.contains("\ng_tank.bfg.hardpoint Chassis\n"),
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn test_exec_and_writeconfig() {
+        let mut cvars = Cvars::default();
+        assert_eq!(
+            write_config(&cvars),
+            "# Cvars which differ from the defaults, generated by writeconfig\n"
+        );
+
+        let text = "# comment\n\n  ai false  \nsv_map_rotation Atrium, Bunkers (2)\n\
+                    g_match_points_limit x\nnope 1\nbots_max\n";
+        let errors = exec_lines(&mut cvars, text, Path::new(""), &mut Vec::new());
+        let lines: Vec<_> = errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![5, 6, 7]);
+        assert_eq!(errors[2].1, "missing value for bots_max");
+        assert!(!cvars.ai);
+        let names = vec!["Atrium".to_owned(), "Bunkers (2)".to_owned()];
+        assert_eq!(cvars.sv_map_rotation, MapList(names));
+
+        let written = write_config(&cvars);
+        assert!(written.ends_with("\nai false\nsv_map_rotation Atrium, Bunkers (2)\n"));
+        let mut loaded = Cvars::default();
+        assert!(exec_lines(&mut loaded, &written, Path::new(""), &mut Vec::new()).is_empty());
+        assert_eq!(write_config(&loaded), written);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = env::temp_dir().join(format!("rec-wars-config-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(dir.join("main.cfg"), "bots_max 3\nexec shared/common.cfg\n").unwrap();
+        fs::write(
+            dir.join("shared/common.cfg"),
+            "ai false\ninclude ../loop.cfg\n",
+        )
+        .unwrap();
+        fs::write(dir.join("loop.cfg"), "exec shared/common.cfg\n").unwrap();
+
+        let mut cvars = Cvars::default();
+        let err = exec(&mut cvars, dir.join("main.cfg").to_str().unwrap()).unwrap_err();
+        assert!(err.contains("recursive exec"), "{err}");
+        assert_eq!(cvars.bots_max, 3);
+        assert!(!cvars.ai);
+
+        let mut commands = Commands(&mut cvars);
+        commands.set_str("bots_max", "4").unwrap();
+        assert!(commands.get_string("exec").is_err());
+        let err = commands.set_str("exec", "does/not/exist.cfg").unwrap_err();
+        assert!(err.starts_with("failed to read"), "{err}");
+        // Only shows usage, saving needs an explicit path.
+        assert!(commands.get_string("writeconfig").is_err());
+        let saved = dir.join("saved.cfg");
+        commands
+            .set_str("writeconfig", saved.to_str().unwrap())
+            .unwrap();
+        assert!(fs::read_to_string(saved).unwrap().contains("bots_max 4"));
+        assert_eq!(cvars.bots_max, 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_balance() {
+        let mut cvars = Cvars {
+            d_seed: 42,
+            bots_max: 3,
+            ..Cvars::default()
+        };
+        let changes = load_balance(&mut cvars, Balance::RecWar);
+        assert!(
+            changes.contains(&"bots_max 3 -> 20".to_owned()),
+            "{changes:?}"
+        );
+        assert!(changes
+            .iter()
+            .any(|change| change.starts_with("g_railgun.speed 2500 -> ")));
+        assert_eq!(cvars.d_seed, 42);
+
+        // Client cvars are not part of the balance.
+        cvars.cl_zoom = 2.0;
+        cvars.r_fullscreen = true;
+        let changes = load_balance(&mut cvars, Balance::RecWars);
+        assert!(changes.iter().all(|change| !change.starts_with("cl_zoom ")));
+        assert_eq!(cvars.cl_zoom, 2.0);
+        assert!(cvars.r_fullscreen);
+        cvars.cl_zoom = Cvars::default().cl_zoom;
+        cvars.r_fullscreen = false;
+        load_balance(&mut cvars, Balance::RecWar);
+
+        let mut commands = Commands(&mut cvars);
+        assert!(commands
+            .get_string("balance")
+            .unwrap()
+            .contains("rec_war ("));
+        commands.set_str("balance", "rec_wars").unwrap();
+        commands.set_str("balance", "quake").unwrap_err();
+        assert_eq!(
+            changed_cvars(&Cvars::default(), &cvars),
+            vec!["d_seed 0 -> 42"]
+        );
+    }
+
+    #[test]
+    fn test_binds() {
+        use macroquad::input::KeyCode;
+
+        use crate::binds::Action;
+
+        let mut cvars = Cvars {
+            sv_cheats: false,
+            ..Cvars::default()
+        };
+        let text = "bind F fire\nunbind Space\nbind2 kp1=left\nunbind2 Z\nbind2 Z\nunbind nope\n";
+        let errors = exec_lines(&mut cvars, text, Path::new(""), &mut Vec::new());
+        let lines: Vec<_> = errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![4, 5, 6]);
+        assert_eq!(errors[0].1, "Z is not bound");
+        assert_eq!(cvars.cl_binds[0].action(KeyCode::F), Some(Action::Fire));
+        assert_eq!(cvars.cl_binds[0].action(KeyCode::Space), None);
+        assert_eq!(cvars.cl_binds[1].action(KeyCode::Kp1), Some(Action::Left));
+
+        let written = write_config(&cvars);
+        assert!(written.contains("\nunbind all\nbind A left\n"), "{written}");
+        assert!(written.ends_with("bind2 Kp1 left\n"), "{written}");
+        let mut loaded = Cvars::default();
+        assert!(exec_lines(&mut loaded, &written, Path::new(""), &mut Vec::new()).is_empty());
+        assert_eq!(loaded.cl_binds, cvars.cl_binds);
+
+        let mut commands = Commands(&mut cvars);
+        commands.set_str("unbind2", "all").unwrap();
+        commands.set_str("bind2", "Up=fire").unwrap();
+        assert_eq!(
+            commands.get_string("bind2").unwrap(),
+            "usage: bind2 <key>=<action>, current binds: fire: Up"
+        );
+        assert!(commands.get_string("unbind").is_err());
+        commands.set_str("bind4", "Kp8=up").unwrap();
+        assert!(commands.set_str("bind5", "Kp8=up").is_err());
+        assert!(commands.set_str("bind1", "Kp8=up").is_err());
+        assert!(commands.get_string("binder").is_err());
+        assert!(write_config(commands.0).ends_with("unbind4 all\nbind4 Kp8 up\n"));
+
+        cvars.sv_cheats = true;
+        let binds = cvars.cl_binds.clone();
+        load_balance(&mut cvars, Balance::RecWar);
+        assert_eq!(cvars.cl_binds, binds);
+    }
+
+    #[test]
+    fn test_cheat_protection() {
+        let mut cvars = Cvars::default();
+        let text = "sv_cheats false\nbots_max 3\nhud_names false\nnope 1\n";
+        let errors = exec_lines(&mut cvars, text, Path::new(""), &mut Vec::new());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0],
+            (
+                2,
+                "bots_max is cheat protected, set sv_cheats true first".to_owned()
+            )
+        );
+        assert!(!errors[1].1.contains("cheat"), "{}", errors[1].1);
+        assert!(!cvars.hud_names);
+        assert_eq!(cvars.bots_max, Cvars::default().bots_max);
+
+        let mut commands = Commands(&mut cvars);
+        assert!(commands.set_str("g_rockets.speed", "1").is_err());
+        assert!(commands.set_str("balance", "rec_war").is_err());
+        assert_eq!(
+            commands.get_string("bots_max").unwrap(),
+            "20 [cheat replicated]"
+        );
+        commands.set_str("sv_cheats", "true").unwrap();
+        commands.set_str("g_rockets.speed", "1").unwrap();
+        assert_eq!(cvars.g_weapon(Weapon::Rockets).speed, 1.0);
+
+        let list = cvar_list(&cvars);
+        assert_eq!(list.len(), Cvars::names().len());
+        assert!(list.contains(&"hud_names false [client]".to_owned()));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut cvars = Cvars::default();
+        // Config files report the problem and keep going.
+        let text = "d_speed -2\nd_speed 2\n";
+        let errors = exec_lines(&mut cvars, text, Path::new(""), &mut Vec::new());
+        assert_eq!(errors, vec![(1, "d_speed must be zero or more".to_owned())]);
+        assert_eq!(cvars.d_speed, 2.0);
+    }
+}
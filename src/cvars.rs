@@ -1,408 +1,366 @@
 //! Console variables - configuration options for anything and everything.
 
-use std::default::Default;
+use std::{
+    convert::Infallible,
+    default::Default,
+    fmt::{self, Formatter},
+    str::FromStr,
+};
 
 use cvars::SetGet;
-use strum_macros::{Display, EnumString};
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::{
+    binds::{Binds, MAX_LOCAL_PLAYERS},
+    entities::{Hitbox, VehicleType, Weapon, VEHICLES_CNT, WEAPS_CNT},
+    map::Vec2f,
+    map_gen::{Biome, Symmetry},
+};
+
+/// Declares the `Cvars` struct and lists its fields in `Cvars::FIELDS`
+/// so the list of cvar names can't get out of sync with the struct.
+macro_rules! cvars_struct {
+    (
+        $( #[$meta:meta] )*
+        pub struct $name:ident {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $ty:ty, )*
+        }
+    ) => {
+        $( #[$meta] )*
+        pub struct $name {
+            $( $( #[$field_meta] )* pub $field: $ty, )*
+        }
 
-use crate::{entities::Hitbox, entities::VehicleType, entities::Weapon, map::Vec2f};
+        impl $name {
+            /// All fields in the order they're declared, including those which are not cvars.
+            const FIELDS: &'static [&'static str] = &[ $( stringify!($field), )* ];
+        }
+    };
+}
 
-/// Console variables - configuration options for anything and everything.
-///
-/// Prefix meanings:
-/// cl_ is client
-/// d_ is debug
-/// g_ is gameplay
-/// hud_ is the heads-up display
-/// r_ is rendering
-/// sv_ is server administration + performance
-#[derive(Debug, Clone, SetGet)]
-#[allow(missing_copy_implementations)]
-pub struct Cvars {
-    // Long-term this needs some kind of better system to reduce duplication / manual work.
-    // Would be nice to keep alphabetically.
-    //  |
-    //  v
-    /// Master switch for AI - disable if you want stationary targets
-    pub ai: bool,
-
-    pub bots_max: usize,
-
-    pub cl_cluster_bomb_size: f64,
-
-    pub cl_machine_gun_trail_length: f64,
-    pub cl_machine_gun_trail_thickness: f64,
-
-    pub cl_railgun_trail_thickness: f64,
-    pub cl_railgun_trail_duration: f64,
-
-    pub cl_spawn_indicator_animation_time: f64,
-    pub cl_spawn_indicator_blinking_period: f64,
-    pub cl_spawn_indicator_duration: f64,
-    pub cl_spawn_indicator_square_side_begin: f32,
-    pub cl_spawn_indicator_square_side_end: f32,
-    pub cl_spawn_indicator_thickness: f32,
-
-    pub con_background_alpha: f32,
-    pub con_prompt_group_x: f32,
-    pub con_prompt_group_y_offset: f32,
-    pub con_height_fraction: f32,
-    pub con_history_line_font_size: f32,
-    pub con_history_line_height: f32,
-    pub con_history_x: f32,
-    pub con_history_y_offset: f32,
-    pub con_prompt_label_x: f32,
-    pub con_prompt_label_y_offset: f32,
-
-    /// "Temporary" cvar for quick testing. Normally unused but kept here
-    /// so I don't have to add a cvar each time I want a quick toggle.
-    pub d_dbg: bool,
-
-    /// Master switch for debug output - the d_draw_* group.
-    pub d_draw: bool,
-    pub d_draw_crosses: bool,
-    pub d_draw_hitboxes: bool,
-    pub d_draw_lines: bool,
-    pub d_draw_lines_ends_length: f64,
-    pub d_draw_perf: bool,
-    pub d_draw_text: bool,
-    pub d_draw_text_line_height: f64,
-    pub d_draw_world_text: bool,
-    pub d_explosion_radius: bool,
-    /// Draw FPS counter. Intentionally not in the d_draw_* group
-    /// so I can easily check perf with and without the other debug output.
-    pub d_fps: bool,
-    pub d_fps_period: f64,
-    pub d_fps_x: f64,
-    pub d_fps_y: f64,
-    /// Display the last pressed key. Useful for debugging MQ's issues with keyboard layouts.
-    pub d_last_key: bool,
-    pub d_tickrate_remaining: bool,
-    pub d_timing_samples: usize,
-    pub d_tracing: bool,
-    /// The seed to initialize the RNG.
+cvars_struct! {
+    /// Console variables - configuration options for anything and everything.
     ///
-    /// This is not very helpful by itself because by the time you can change cvars in the console,
-    /// the seed has already been used. However, in the macroquad desktop version,
-    /// you can set it on the command line.
+    /// Prefix meanings:
+    /// cl_ is client
+    /// d_ is debug
+    /// g_ is gameplay
+    /// hud_ is the heads-up display
+    /// r_ is rendering
+    /// sv_ is server administration + performance
     ///
-    /// If the seed is 0 at match start, the cvar is changed to the current time and that is used as seed.
-    /// This means you can look at the cvar's value later and know what seed you need to replay the same game.
-    pub d_seed: u64,
-    /// Change speed of everything in the game
-    pub d_speed: f64,
-
-    /// Hit points. Recommended values are between 1 and 500, original RecWar used 100 as default.
-    ///
-    /// Note that the actual number of hitpoints depends on vehicle type, this is just the base value.
-    /// By default, the tank uses this value, other vehicles scale it by some multiplier.
-    pub g_armor: f64,
-
-    pub g_bfg_beam_damage_per_sec: f64,
-    pub g_bfg_beam_range: f64,
-    pub g_bfg_damage_direct: f64,
-    pub g_bfg_explosion_damage: f64,
-    pub g_bfg_explosion_radius: f64,
-    pub g_bfg_explosion_scale: f64,
-    pub g_bfg_radius: f64,
-    pub g_bfg_reload_ammo: u32,
-    pub g_bfg_reload_time: f64,
-    pub g_bfg_speed: f64,
-    pub g_bfg_vehicle_velocity_factor: f64,
-
-    pub g_cluster_bomb_count: i32,
-    pub g_cluster_bomb_damage_direct: f64,
-    pub g_cluster_bomb_explosion_damage: f64,
-    pub g_cluster_bomb_explosion_radius: f64,
-    pub g_cluster_bomb_explosion_scale: f64,
-    pub g_cluster_bomb_reload_ammo: u32,
-    pub g_cluster_bomb_reload_time: f64,
-    pub g_cluster_bomb_shadow_alpha: f64,
-    pub g_cluster_bomb_shadow_x: f64,
-    pub g_cluster_bomb_shadow_y: f64,
-    pub g_cluster_bomb_speed: f64,
-    pub g_cluster_bomb_speed_spread_forward: f64,
-    pub g_cluster_bomb_speed_spread_gaussian: bool,
-    pub g_cluster_bomb_speed_spread_sideways: f64,
-    pub g_cluster_bomb_time: f64,
-    pub g_cluster_bomb_time_spread: f64,
-    pub g_cluster_bomb_vehicle_velocity_factor: f64,
-
-    pub g_ffa_score_kill: i32,
-    pub g_ffa_score_death: i32,
-
-    pub g_homing_missile_damage_direct: f64,
-    pub g_homing_missile_explosion_damage: f64,
-    pub g_homing_missile_explosion_radius: f64,
-    pub g_homing_missile_explosion_scale: f64,
-    pub g_homing_missile_reload_ammo: u32,
-    pub g_homing_missile_reload_time: f64,
-    pub g_homing_missile_speed_initial: f64,
-    pub g_homing_missile_vehicle_velocity_factor: f64,
-
-    pub g_machine_gun_angle_spread: f64,
-    pub g_machine_gun_damage: f64,
-    pub g_machine_gun_refire: f64,
-    pub g_machine_gun_reload_ammo: u32,
-    pub g_machine_gun_reload_time: f64,
-    pub g_machine_gun_speed: f64,
-    pub g_machine_gun_vehicle_velocity_factor: f64,
-
-    pub g_guided_missile_accel_forward: f64,
-    pub g_guided_missile_damage_direct: f64,
-    pub g_guided_missile_explosion_damage: f64,
-    pub g_guided_missile_explosion_radius: f64,
-    pub g_guided_missile_explosion_scale: f64,
-    pub g_guided_missile_friction_const: f64,
-    pub g_guided_missile_friction_linear: f64,
-    pub g_guided_missile_reload_ammo: u32,
-    pub g_guided_missile_reload_time: f64,
-    pub g_guided_missile_speed_initial: f64,
-    pub g_guided_missile_speed_max: f64,
-    pub g_guided_missile_turn_effectiveness: f64,
-    pub g_guided_missile_turn_rate_increase: f64,
-    pub g_guided_missile_turn_rate_friction_const: f64,
-    pub g_guided_missile_turn_rate_friction_linear: f64,
-    pub g_guided_missile_turn_rate_max: f64,
-    pub g_guided_missile_vehicle_velocity_factor: f64,
-
-    pub g_hardpoint_hovercraft_machine_gun: Hardpoint,
-    pub g_hardpoint_hovercraft_machine_gun_x: f64,
-    pub g_hardpoint_hovercraft_machine_gun_y: f64,
-    pub g_hardpoint_hovercraft_railgun: Hardpoint,
-    pub g_hardpoint_hovercraft_railgun_x: f64,
-    pub g_hardpoint_hovercraft_railgun_y: f64,
-    pub g_hardpoint_hovercraft_cluster_bomb: Hardpoint,
-    pub g_hardpoint_hovercraft_cluster_bomb_x: f64,
-    pub g_hardpoint_hovercraft_cluster_bomb_y: f64,
-    pub g_hardpoint_hovercraft_rockets: Hardpoint,
-    pub g_hardpoint_hovercraft_rockets_x: f64,
-    pub g_hardpoint_hovercraft_rockets_y: f64,
-    pub g_hardpoint_hovercraft_homing_missile: Hardpoint,
-    pub g_hardpoint_hovercraft_homing_missile_x: f64,
-    pub g_hardpoint_hovercraft_homing_missile_y: f64,
-    pub g_hardpoint_hovercraft_guided_missile: Hardpoint,
-    pub g_hardpoint_hovercraft_guided_missile_x: f64,
-    pub g_hardpoint_hovercraft_guided_missile_y: f64,
-    pub g_hardpoint_hovercraft_bfg: Hardpoint,
-    pub g_hardpoint_hovercraft_bfg_x: f64,
-    pub g_hardpoint_hovercraft_bfg_y: f64,
-
-    pub g_hardpoint_hummer_machine_gun: Hardpoint,
-    pub g_hardpoint_hummer_machine_gun_x: f64,
-    pub g_hardpoint_hummer_machine_gun_y: f64,
-    pub g_hardpoint_hummer_railgun: Hardpoint,
-    pub g_hardpoint_hummer_railgun_x: f64,
-    pub g_hardpoint_hummer_railgun_y: f64,
-    pub g_hardpoint_hummer_cluster_bomb: Hardpoint,
-    pub g_hardpoint_hummer_cluster_bomb_x: f64,
-    pub g_hardpoint_hummer_cluster_bomb_y: f64,
-    pub g_hardpoint_hummer_rockets: Hardpoint,
-    pub g_hardpoint_hummer_rockets_x: f64,
-    pub g_hardpoint_hummer_rockets_y: f64,
-    pub g_hardpoint_hummer_homing_missile: Hardpoint,
-    pub g_hardpoint_hummer_homing_missile_x: f64,
-    pub g_hardpoint_hummer_homing_missile_y: f64,
-    pub g_hardpoint_hummer_guided_missile: Hardpoint,
-    pub g_hardpoint_hummer_guided_missile_x: f64,
-    pub g_hardpoint_hummer_guided_missile_y: f64,
-    pub g_hardpoint_hummer_bfg: Hardpoint,
-    pub g_hardpoint_hummer_bfg_x: f64,
-    pub g_hardpoint_hummer_bfg_y: f64,
-
-    pub g_hardpoint_tank_machine_gun: Hardpoint,
-    pub g_hardpoint_tank_machine_gun_x: f64,
-    pub g_hardpoint_tank_machine_gun_y: f64,
-    pub g_hardpoint_tank_railgun: Hardpoint,
-    pub g_hardpoint_tank_railgun_x: f64,
-    pub g_hardpoint_tank_railgun_y: f64,
-    pub g_hardpoint_tank_cluster_bomb: Hardpoint,
-    pub g_hardpoint_tank_cluster_bomb_x: f64,
-    pub g_hardpoint_tank_cluster_bomb_y: f64,
-    pub g_hardpoint_tank_rockets: Hardpoint,
-    pub g_hardpoint_tank_rockets_x: f64,
-    pub g_hardpoint_tank_rockets_y: f64,
-    pub g_hardpoint_tank_homing_missile: Hardpoint,
-    pub g_hardpoint_tank_homing_missile_x: f64,
-    pub g_hardpoint_tank_homing_missile_y: f64,
-    pub g_hardpoint_tank_guided_missile: Hardpoint,
-    pub g_hardpoint_tank_guided_missile_x: f64,
-    pub g_hardpoint_tank_guided_missile_y: f64,
-    pub g_hardpoint_tank_bfg: Hardpoint,
-    pub g_hardpoint_tank_bfg_x: f64,
-    pub g_hardpoint_tank_bfg_y: f64,
-
-    pub g_hitcircle_radius: f64, // TODO proper hitbox
-
-    pub g_hovercraft_armor_scale: f64,
-    pub g_hovercraft_accel_backward: f64,
-    pub g_hovercraft_accel_forward: f64,
-    pub g_hovercraft_friction_const: f64,
-    pub g_hovercraft_friction_linear: f64,
-    pub g_hovercraft_maxs_x: f64,
-    pub g_hovercraft_maxs_y: f64,
-    pub g_hovercraft_mins_x: f64,
-    pub g_hovercraft_mins_y: f64,
-    pub g_hovercraft_speed_max: f64,
-    pub g_hovercraft_steering_car: f64,
-    pub g_hovercraft_turn_effectiveness: f64,
-    pub g_hovercraft_turn_rate_friction_const: f64,
-    pub g_hovercraft_turn_rate_friction_linear: f64,
-    pub g_hovercraft_turn_rate_increase: f64,
-    pub g_hovercraft_turn_rate_max: f64,
-    pub g_hovercraft_turret_offset_chassis_x: f64,
-    pub g_hovercraft_turret_offset_chassis_y: f64,
-    pub g_hovercraft_turret_offset_turret_x: f64,
-    pub g_hovercraft_turret_offset_turret_y: f64,
-
-    pub g_hummer_armor_scale: f64,
-    pub g_hummer_accel_backward: f64,
-    pub g_hummer_accel_forward: f64,
-    pub g_hummer_friction_const: f64,
-    pub g_hummer_friction_linear: f64,
-    pub g_hummer_maxs_x: f64,
-    pub g_hummer_maxs_y: f64,
-    pub g_hummer_mins_x: f64,
-    pub g_hummer_mins_y: f64,
-    pub g_hummer_speed_max: f64,
-    pub g_hummer_steering_car: f64,
-    pub g_hummer_turn_effectiveness: f64,
-    pub g_hummer_turn_rate_friction_const: f64,
-    pub g_hummer_turn_rate_friction_linear: f64,
-    pub g_hummer_turn_rate_increase: f64,
-    pub g_hummer_turn_rate_max: f64,
-    pub g_hummer_turret_offset_chassis_x: f64,
-    pub g_hummer_turret_offset_chassis_y: f64,
-    pub g_hummer_turret_offset_turret_x: f64,
-    pub g_hummer_turret_offset_turret_y: f64,
-
-    pub g_railgun_damage: f64,
-    pub g_railgun_push: f64,
-    pub g_railgun_reload_ammo: u32,
-    pub g_railgun_reload_time: f64,
-    pub g_railgun_speed: f64,
-    pub g_railgun_vehicle_velocity_factor: f64,
-
-    pub g_respawn_delay: f64,
-
-    pub g_rockets_damage_direct: f64,
-    pub g_rockets_explosion_damage: f64,
-    pub g_rockets_explosion_radius: f64,
-    pub g_rockets_explosion_scale: f64,
-    pub g_rockets_refire: f64,
-    pub g_rockets_reload_ammo: u32,
-    pub g_rockets_reload_time: f64,
-    pub g_rockets_speed: f64,
-    pub g_rockets_vehicle_velocity_factor: f64,
-
-    pub g_self_destruct_damage_center: f64,
-    pub g_self_destruct_damage_edge: f64,
-    pub g_self_destruct_explosion_scale: f64, // TODO radius
-    pub g_self_destruct_radius: f64,
-
-    pub g_tank_armor_scale: f64,
-    pub g_tank_accel_backward: f64,
-    pub g_tank_accel_forward: f64,
-    pub g_tank_friction_const: f64,
-    pub g_tank_friction_linear: f64,
-    pub g_tank_maxs_x: f64,
-    pub g_tank_maxs_y: f64,
-    pub g_tank_mins_x: f64,
-    pub g_tank_mins_y: f64,
-    pub g_tank_speed_max: f64,
-    pub g_tank_steering_car: f64,
-    pub g_tank_turn_effectiveness: f64,
-    pub g_tank_turn_rate_friction_const: f64,
-    pub g_tank_turn_rate_friction_linear: f64,
-    pub g_tank_turn_rate_increase: f64,
-    pub g_tank_turn_rate_max: f64,
-    pub g_tank_turret_offset_chassis_x: f64,
-    pub g_tank_turret_offset_chassis_y: f64,
-    pub g_tank_turret_offset_turret_x: f64,
-    pub g_tank_turret_offset_turret_y: f64,
-
-    pub g_turret_turn_speed_deg: f64,
-    pub g_turret_turn_step_angle_deg: f64,
-
-    pub hud_ammo_x: f64,
-    pub hud_ammo_y: f64,
-    /// Original RecWar had 99.
-    pub hud_ammo_width: f64,
-    /// Original RecWar had 4.
-    pub hud_ammo_height: f64,
-
-    pub hud_hp_x: f64,
-    pub hud_hp_y: f64,
-    /// Original RecWar had 99.
-    pub hud_hp_width: f64,
-    /// Original RecWar had 9.
-    pub hud_hp_height: f64,
-
-    pub hud_names: bool,
-    pub hud_names_alpha: f64,
-    pub hud_names_brightness: f64,
-    pub hud_names_font_size: f64,
-    pub hud_names_shadow_alpha: f64,
-    pub hud_names_shadow_x: f32,
-    pub hud_names_shadow_y: f32,
-    pub hud_names_x: f64,
-    pub hud_names_y: f64,
-
-    pub hud_missile_indicator_dash_length: f64,
-    pub hud_missile_indicator_radius: f64,
-
-    pub hud_pause_font_size: f64,
-    pub hud_pause_shadow_x: f32,
-    pub hud_pause_shadow_y: f32,
-
-    pub hud_ranking_font_size: f64,
-    /// Original RW uses 1
-    pub hud_ranking_shadow_x: f32,
-    /// Original RW uses 1
-    pub hud_ranking_shadow_y: f32,
-    pub hud_ranking_x: f64,
-    pub hud_ranking_y: f64,
-
-    pub hud_score_font_size: f64,
-    /// Original RW uses 2
-    pub hud_score_shadow_x: f32,
-    /// Original RW uses 2
-    pub hud_score_shadow_y: f32,
-    pub hud_score_x: f64,
-    pub hud_score_y: f64,
-
-    pub hud_scoreboard_font_size: f64,
-    pub hud_scoreboard_line_height: f64,
-    /// NB: these shadows absolutely murder performance in firefox (chromum is ok)
-    pub hud_scoreboard_shadow_x: f32,
-    pub hud_scoreboard_shadow_y: f32,
-    pub hud_scoreboard_width_deaths: f32,
-    pub hud_scoreboard_width_kills: f32,
-    pub hud_scoreboard_width_name: f32,
-    pub hud_scoreboard_width_points: f32,
-
-    pub hud_weapon_icon_shadow_alpha: f64,
-    pub hud_weapon_icon_shadow_x: f32,
-    pub hud_weapon_icon_shadow_y: f32,
-    pub hud_weapon_icon_x: f64,
-    pub hud_weapon_icon_y: f64,
-
-    /// This is in a way the opposite of smoothing
-    pub r_align_to_pixels_background: bool,
-    pub r_align_to_pixels_text: bool,
-    pub r_draw_cluster_bombs: bool,
-    pub r_explosion_duration: f64,
-    pub r_explosions_reverse_order: bool,
-    pub r_smoothing: bool,
-    pub r_splitscreen_gap: f64,
-
-    /// LATER fix - Does not work in MQ: https://github.com/not-fl3/macroquad/issues/264
-    pub sv_auto_pause_on_minimize: bool,
-    /// LATER fix - Does not work in MQ: https://github.com/not-fl3/macroquad/issues/264
-    pub sv_auto_unpause_on_restore: bool,
-
-    pub sv_tickrate_mode: TickrateMode,
-    pub sv_tickrate_fixed_fps: f64,
+    /// The prefix also determines the cvar's flags, see `CvarFlags`.
+    #[derive(Debug, Clone, SetGet)]
+    #[allow(missing_copy_implementations)]
+    pub struct Cvars {
+        // Long-term this needs some kind of better system to reduce duplication / manual work.
+        // Would be nice to keep alphabetically.
+        //  |
+        //  v
+        /// Master switch for AI - disable if you want stationary targets
+        pub ai: bool,
+
+        pub bots_max: usize,
+
+        /// Key bindings indexed by local player, changed by the `bind` commands
+        #[cvars(skip)]
+        pub cl_binds: [Binds; MAX_LOCAL_PLAYERS],
+
+        /// Shift the camera from the player's vehicle toward where it's heading or aiming
+        pub cl_camera_look_ahead: LookAhead,
+        /// Max distance of the camera from the vehicle with `cl_camera_look_ahead`
+        pub cl_camera_look_ahead_distance: f64,
+        /// With `cl_camera_look_ahead Velocity`, how many seconds of movement ahead of the vehicle to show
+        pub cl_camera_look_ahead_time: f64,
+        /// How fast the look-ahead follows the vehicle - higher is faster, 0 means instantly
+        pub cl_camera_look_ahead_speed: f64,
+
+        pub cl_cluster_bomb_size: f64,
+
+        /// Camera speed in the map editor in pixels per second
+        pub cl_editor_scroll_speed: f64,
+
+        /// How far the sticks have to move (0 to 1) before they count as pressed
+        pub cl_gamepad_deadzone: f64,
+        /// Which gamepad (counting from 1) controls local player 1, 0 for none.
+        pub cl_gamepad_player1: usize,
+        /// Which gamepad (counting from 1) controls local player 2 in splitscreen, 0 for none.
+        pub cl_gamepad_player2: usize,
+        pub cl_gamepad_player3: usize,
+        pub cl_gamepad_player4: usize,
+        /// How far the triggers have to be pulled (0 to 1) to fire
+        pub cl_gamepad_trigger_threshold: f64,
+        pub cl_gamepads: bool,
+
+        pub cl_machine_gun_trail_length: f64,
+        pub cl_machine_gun_trail_thickness: f64,
+
+        /// Player 1's turret points at the mouse cursor and the left mouse button fires
+        pub cl_mouse_aim: bool,
+
+        pub cl_railgun_trail_thickness: f64,
+        pub cl_railgun_trail_duration: f64,
+
+        pub cl_spawn_indicator_animation_time: f64,
+        pub cl_spawn_indicator_blinking_period: f64,
+        pub cl_spawn_indicator_duration: f64,
+        pub cl_spawn_indicator_square_side_begin: f32,
+        pub cl_spawn_indicator_square_side_end: f32,
+        pub cl_spawn_indicator_thickness: f32,
+
+        /// Screen pixels per world pixel - more than 1 zooms in, less zooms out.
+        ///
+        /// Changed by the mouse wheel.
+        pub cl_zoom: f64,
+        /// Zoom out on maps bigger than `cl_zoom_auto_tiles`
+        pub cl_zoom_auto: bool,
+        /// The most `cl_zoom_auto` zooms out, relative to `cl_zoom`
+        pub cl_zoom_auto_min: f64,
+        /// Maps with more tiles than this along either side are zoomed out proportionally with `cl_zoom_auto`
+        pub cl_zoom_auto_tiles: f64,
+        /// How fast the zoom changes - higher is faster, 0 means instantly
+        pub cl_zoom_speed: f64,
+        /// Each mouse wheel step multiplies or divides `cl_zoom` by this
+        pub cl_zoom_step: f64,
+
+        pub con_background_alpha: f32,
+        pub con_prompt_group_x: f32,
+        pub con_prompt_group_y_offset: f32,
+        pub con_height_fraction: f32,
+        pub con_history_line_font_size: f32,
+        pub con_history_line_height: f32,
+        pub con_history_x: f32,
+        pub con_history_y_offset: f32,
+        pub con_prompt_label_x: f32,
+        pub con_prompt_label_y_offset: f32,
+
+        /// "Temporary" cvar for quick testing. Normally unused but kept here
+        /// so I don't have to add a cvar each time I want a quick toggle.
+        pub d_dbg: bool,
+
+        /// Master switch for debug output - the d_draw_* group.
+        pub d_draw: bool,
+        pub d_draw_crosses: bool,
+        pub d_draw_hitboxes: bool,
+        pub d_draw_lines: bool,
+        pub d_draw_lines_ends_length: f64,
+        pub d_draw_perf: bool,
+        pub d_draw_text: bool,
+        pub d_draw_text_line_height: f64,
+        pub d_draw_world_text: bool,
+        pub d_explosion_radius: bool,
+        /// Draw FPS counter. Intentionally not in the d_draw_* group
+        /// so I can easily check perf with and without the other debug output.
+        pub d_fps: bool,
+        pub d_fps_period: f64,
+        pub d_fps_x: f64,
+        pub d_fps_y: f64,
+        /// Display the last pressed key. Useful for debugging MQ's issues with keyboard layouts.
+        pub d_last_key: bool,
+        pub d_tickrate_remaining: bool,
+        pub d_timing_samples: usize,
+        pub d_tracing: bool,
+        /// The seed to initialize the RNG.
+        ///
+        /// This is not very helpful by itself because by the time you can change cvars in the console,
+        /// the seed has already been used. However, in the macroquad desktop version,
+        /// you can set it on the command line.
+        ///
+        /// If the seed is 0 at match start, the cvar is changed to the current time and that is used as seed.
+        /// This means you can look at the cvar's value later and know what seed you need to replay the same game.
+        pub d_seed: u64,
+        /// Change speed of everything in the game
+        pub d_speed: f64,
+
+        /// Hit points. Recommended values are between 1 and 500, original RecWar used 100 as default.
+        ///
+        /// Note that the actual number of hitpoints depends on vehicle type, this is just the base value.
+        /// By default, the tank uses this value, other vehicles scale it by some multiplier.
+        pub g_armor: f64,
+
+        /// Stats shared by all weapons indexed by `Weapon`, cvars like `g_rockets.speed`
+        #[cvars(skip)]
+        pub g_weapons: [WeaponStats; WEAPS_CNT as usize],
+        /// Indexed by `VehicleType`, cvars like `g_tank.speed_max`
+        #[cvars(skip)]
+        pub g_vehicles: [VehicleStats; VEHICLES_CNT],
+        /// Where weapons are mounted, indexed by `VehicleType` and `Weapon`, cvars like `g_tank.rockets.x`
+        #[cvars(skip)]
+        pub g_hardpoints: [[HardpointStats; WEAPS_CNT as usize]; VEHICLES_CNT],
+
+        pub g_bfg_beam_damage_per_sec: f64,
+        pub g_bfg_beam_range: f64,
+        pub g_bfg_radius: f64,
+
+        pub g_cluster_bomb_count: i32,
+        pub g_cluster_bomb_shadow_alpha: f64,
+        pub g_cluster_bomb_shadow_x: f64,
+        pub g_cluster_bomb_shadow_y: f64,
+        pub g_cluster_bomb_speed_spread_forward: f64,
+        pub g_cluster_bomb_speed_spread_gaussian: bool,
+        pub g_cluster_bomb_speed_spread_sideways: f64,
+        pub g_cluster_bomb_time: f64,
+        pub g_cluster_bomb_time_spread: f64,
+
+        pub g_ffa_score_kill: i32,
+        pub g_ffa_score_death: i32,
+
+        pub g_machine_gun_angle_spread: f64,
+
+        pub g_guided_missile_accel_forward: f64,
+        pub g_guided_missile_friction_const: f64,
+        pub g_guided_missile_friction_linear: f64,
+        pub g_guided_missile_speed_max: f64,
+        pub g_guided_missile_turn_effectiveness: f64,
+        pub g_guided_missile_turn_rate_increase: f64,
+        pub g_guided_missile_turn_rate_friction_const: f64,
+        pub g_guided_missile_turn_rate_friction_linear: f64,
+        pub g_guided_missile_turn_rate_max: f64,
+
+        pub g_hitcircle_radius: f64, // TODO proper hitbox
+
+        /// The match ends when a player reaches this many points (0 means no limit)
+        pub g_match_points_limit: i32,
+        /// The match ends after this many seconds of game time (0 means no limit)
+        pub g_match_time_limit: f64,
+
+        pub g_railgun_push: f64,
+
+        pub g_respawn_delay: f64,
+
+        pub g_self_destruct_damage_center: f64,
+        pub g_self_destruct_damage_edge: f64,
+        pub g_self_destruct_explosion_scale: f64, // TODO radius
+        pub g_self_destruct_radius: f64,
+
+        pub g_turret_turn_speed_deg: f64,
+        pub g_turret_turn_step_angle_deg: f64,
+        /// What happens when a turret step is pressed while the turret is still turning
+        pub g_turret_turn_mode: TurretTurnMode,
+
+        pub hud_ammo_x: f64,
+        pub hud_ammo_y: f64,
+        /// Original RecWar had 99.
+        pub hud_ammo_width: f64,
+        /// Original RecWar had 4.
+        pub hud_ammo_height: f64,
+
+        /// Show the list of key bindings (toggled by F1)
+        pub hud_binds: bool,
+        pub hud_binds_font_size: f64,
+
+        /// Drawn at the mouse cursor with `cl_mouse_aim`
+        pub hud_crosshair_radius: f64,
+
+        pub hud_hp_x: f64,
+        pub hud_hp_y: f64,
+        /// Original RecWar had 99.
+        pub hud_hp_width: f64,
+        /// Original RecWar had 9.
+        pub hud_hp_height: f64,
+
+        pub hud_names: bool,
+        pub hud_names_alpha: f64,
+        pub hud_names_brightness: f64,
+        pub hud_names_font_size: f64,
+        pub hud_names_shadow_alpha: f64,
+        pub hud_names_shadow_x: f32,
+        pub hud_names_shadow_y: f32,
+        pub hud_names_x: f64,
+        pub hud_names_y: f64,
+
+        pub hud_missile_indicator_dash_length: f64,
+        pub hud_missile_indicator_radius: f64,
+
+        pub hud_pause_font_size: f64,
+        pub hud_pause_shadow_x: f32,
+        pub hud_pause_shadow_y: f32,
+
+        pub hud_ranking_font_size: f64,
+        /// Original RW uses 1
+        pub hud_ranking_shadow_x: f32,
+        /// Original RW uses 1
+        pub hud_ranking_shadow_y: f32,
+        pub hud_ranking_x: f64,
+        pub hud_ranking_y: f64,
+
+        pub hud_score_font_size: f64,
+        /// Original RW uses 2
+        pub hud_score_shadow_x: f32,
+        /// Original RW uses 2
+        pub hud_score_shadow_y: f32,
+        pub hud_score_x: f64,
+        pub hud_score_y: f64,
+
+        pub hud_scoreboard_font_size: f64,
+        pub hud_scoreboard_line_height: f64,
+        /// NB: these shadows absolutely murder performance in firefox (chromum is ok)
+        pub hud_scoreboard_shadow_x: f32,
+        pub hud_scoreboard_shadow_y: f32,
+        pub hud_scoreboard_width_deaths: f32,
+        pub hud_scoreboard_width_kills: f32,
+        pub hud_scoreboard_width_name: f32,
+        pub hud_scoreboard_width_points: f32,
+
+        pub hud_weapon_icon_shadow_alpha: f64,
+        pub hud_weapon_icon_shadow_x: f32,
+        pub hud_weapon_icon_shadow_y: f32,
+        pub hud_weapon_icon_x: f64,
+        pub hud_weapon_icon_y: f64,
+
+        /// This is in a way the opposite of smoothing
+        pub r_align_to_pixels_background: bool,
+        pub r_align_to_pixels_text: bool,
+        pub r_draw_cluster_bombs: bool,
+        pub r_explosion_duration: f64,
+        pub r_explosions_reverse_order: bool,
+        /// Toggled by F11
+        pub r_fullscreen: bool,
+        pub r_smoothing: bool,
+        pub r_splitscreen_gap: f64,
+        pub r_splitscreen_layout: SplitscreenLayout,
+
+        /// LATER fix - Does not work in MQ: https://github.com/not-fl3/macroquad/issues/264
+        pub sv_auto_pause_on_minimize: bool,
+        /// LATER fix - Does not work in MQ: https://github.com/not-fl3/macroquad/issues/264
+        pub sv_auto_unpause_on_restore: bool,
+
+        /// Allow changing cheat-protected cvars (see `CvarFlags`).
+        /// LATER Default to false for online and competitive play.
+        pub sv_cheats: bool,
+
+        /// Maps to rotate through when a match ends - empty means the default list
+        ///
+        /// Not `Copy` so `SetGet` skips it, `get_value` and `set_value` handle it instead.
+        #[cvars(skip)]
+        pub sv_map_rotation: MapList,
+        /// How many maps to offer in the vote after a match
+        pub sv_map_vote_options: usize,
+        /// How long the vote lasts in seconds (it ends earlier if everyone voted)
+        pub sv_map_vote_time: f64,
+
+        /// Parameters for `--generate`, see `map_gen::GenParams`
+        pub sv_mapgen_bases: bool,
+        pub sv_mapgen_biome: Biome,
+        pub sv_mapgen_height: usize,
+        pub sv_mapgen_players: usize,
+        pub sv_mapgen_spawns_per_player: usize,
+        pub sv_mapgen_symmetry: Symmetry,
+        pub sv_mapgen_wall_density: f64,
+        pub sv_mapgen_width: usize,
+
+        pub sv_tickrate_mode: TickrateMode,
+        pub sv_tickrate_fixed_fps: f64,
+        /// Max game time simulated in one update, the rest is dropped.
+        ///
+        /// After a long stall (breakpoint, switched tabs, ...) the game doesn't try to catch up,
+        /// simulating all of it at once would only cause a longer stall.
+        pub sv_tickrate_max_catch_up: f64,
+        /// Max gamelogic ticks in one update with fixed tickrates, see `sv_tickrate_overload`.
+        pub sv_tickrate_max_ticks: usize,
+        /// What to do with the time which doesn't fit into `sv_tickrate_max_ticks`.
+        pub sv_tickrate_overload: TickrateOverload,
+    }
 }
 
 impl Cvars {
@@ -412,20 +370,31 @@ impl Cvars {
     }
 
     /// Create a new Cvars object with an approximation of the original RecWar settings.
-    #[allow(dead_code)] // LATER allow using balance presets again
+    ///
+    /// These are the intentional differences listed in the README,
+    /// everything else is already as close to RecWar as we could measure.
     pub fn new_rec_war() -> Self {
-        Self {
-            // This is 15625 tiles - should be more than enough, biggest original maps have 59.
-            // Can't use infinity - it would break the math.
-            g_railgun_speed: 1_000_000.0,
+        let mut cvars = Self {
+            hud_ammo_width: 99.0,
+            hud_hp_width: 99.0,
             ..Self::default()
+        };
+        // RecWar's railgun is hitscan.
+        // This is 15625 tiles - should be more than enough, biggest original maps have 59.
+        // Can't use infinity - it would break the math.
+        cvars.g_weapons[Weapon::Rail as usize].speed = 1_000_000.0;
+        cvars
+    }
+
+    /// Create a new Cvars object with the given balance preset.
+    pub fn new_balance(balance: Balance) -> Self {
+        match balance {
+            Balance::RecWars => Self::new_rec_wars(),
+            Balance::RecWar => Self::new_rec_war(),
         }
     }
 
     /// Reset this Cvars object to the default RecWars settings.
-    ///
-    /// LATER Allow calling this from the console to change settings on the fly.
-    #[allow(dead_code)]
     pub fn load_rec_wars(&mut self) {
         *self = Self {
             ..Self::new_rec_wars()
@@ -433,375 +402,476 @@ impl Cvars {
     }
 
     /// Reset this Cvars object to an approximation of the original RecWar settings.
-    ///
-    /// LATER Allow calling this from the console to change settings on the fly.
-    #[allow(dead_code)]
     pub fn load_rec_war(&mut self) {
         *self = Self {
             ..Self::new_rec_war()
         }
     }
 
+    /// Reset this Cvars object to the given balance preset.
+    pub fn load_balance(&mut self, balance: Balance) {
+        match balance {
+            Balance::RecWars => self.load_rec_wars(),
+            Balance::RecWar => self.load_rec_war(),
+        }
+    }
+
+    /// Stats of the given weapon which all weapons have.
+    pub fn g_weapon(&self, weapon: Weapon) -> &WeaponStats {
+        &self.g_weapons[weapon as usize]
+    }
+
+    /// `cl_gamepad_player1` etc. for the local player with index `i`.
+    pub fn cl_gamepad_player(&self, i: usize) -> usize {
+        [
+            self.cl_gamepad_player1,
+            self.cl_gamepad_player2,
+            self.cl_gamepad_player3,
+            self.cl_gamepad_player4,
+        ][i]
+    }
+
+    pub fn g_vehicle(&self, veh_type: VehicleType) -> &VehicleStats {
+        &self.g_vehicles[veh_type as usize]
+    }
+
     /// Returns whether the weapon is on the chassis or turret and where relative to that part's center.
     pub fn g_hardpoint(&self, veh_type: VehicleType, weapon: Weapon) -> (Hardpoint, Vec2f) {
-        match veh_type {
-            VehicleType::Tank => match weapon {
-                Weapon::Mg => (
-                    self.g_hardpoint_tank_machine_gun,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_machine_gun_x,
-                        self.g_hardpoint_tank_machine_gun_y,
-                    ),
-                ),
-                Weapon::Rail => (
-                    self.g_hardpoint_tank_railgun,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_railgun_x,
-                        self.g_hardpoint_tank_railgun_y,
-                    ),
-                ),
-                Weapon::Cb => (
-                    self.g_hardpoint_tank_cluster_bomb,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_cluster_bomb_x,
-                        self.g_hardpoint_tank_cluster_bomb_y,
-                    ),
-                ),
-                Weapon::Rockets => (
-                    self.g_hardpoint_tank_rockets,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_rockets_x,
-                        self.g_hardpoint_tank_rockets_y,
-                    ),
-                ),
-                Weapon::Hm => (
-                    self.g_hardpoint_tank_homing_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_homing_missile_x,
-                        self.g_hardpoint_tank_homing_missile_y,
-                    ),
-                ),
-                Weapon::Gm => (
-                    self.g_hardpoint_tank_guided_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_tank_guided_missile_x,
-                        self.g_hardpoint_tank_guided_missile_y,
-                    ),
-                ),
-                Weapon::Bfg => (
-                    self.g_hardpoint_tank_bfg,
-                    Vec2f::new(self.g_hardpoint_tank_bfg_x, self.g_hardpoint_tank_bfg_y),
-                ),
-            },
-            VehicleType::Hovercraft => match weapon {
-                Weapon::Mg => (
-                    self.g_hardpoint_hovercraft_machine_gun,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_machine_gun_x,
-                        self.g_hardpoint_hovercraft_machine_gun_y,
-                    ),
-                ),
-                Weapon::Rail => (
-                    self.g_hardpoint_hovercraft_railgun,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_railgun_x,
-                        self.g_hardpoint_hovercraft_railgun_y,
-                    ),
-                ),
-                Weapon::Cb => (
-                    self.g_hardpoint_hovercraft_cluster_bomb,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_cluster_bomb_x,
-                        self.g_hardpoint_hovercraft_cluster_bomb_y,
-                    ),
-                ),
-                Weapon::Rockets => (
-                    self.g_hardpoint_hovercraft_rockets,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_rockets_x,
-                        self.g_hardpoint_hovercraft_rockets_y,
-                    ),
-                ),
-                Weapon::Hm => (
-                    self.g_hardpoint_hovercraft_homing_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_homing_missile_x,
-                        self.g_hardpoint_hovercraft_homing_missile_y,
-                    ),
-                ),
-                Weapon::Gm => (
-                    self.g_hardpoint_hovercraft_guided_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_guided_missile_x,
-                        self.g_hardpoint_hovercraft_guided_missile_y,
-                    ),
-                ),
-                Weapon::Bfg => (
-                    self.g_hardpoint_hovercraft_bfg,
-                    Vec2f::new(
-                        self.g_hardpoint_hovercraft_bfg_x,
-                        self.g_hardpoint_hovercraft_bfg_y,
-                    ),
-                ),
-            },
-            VehicleType::Hummer => match weapon {
-                Weapon::Mg => (
-                    self.g_hardpoint_hummer_machine_gun,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_machine_gun_x,
-                        self.g_hardpoint_hummer_machine_gun_y,
-                    ),
-                ),
-                Weapon::Rail => (
-                    self.g_hardpoint_hummer_railgun,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_railgun_x,
-                        self.g_hardpoint_hummer_railgun_y,
-                    ),
-                ),
-                Weapon::Cb => (
-                    self.g_hardpoint_hummer_cluster_bomb,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_cluster_bomb_x,
-                        self.g_hardpoint_hummer_cluster_bomb_y,
-                    ),
-                ),
-                Weapon::Rockets => (
-                    self.g_hardpoint_hummer_rockets,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_rockets_x,
-                        self.g_hardpoint_hummer_rockets_y,
-                    ),
-                ),
-                Weapon::Hm => (
-                    self.g_hardpoint_hummer_homing_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_homing_missile_x,
-                        self.g_hardpoint_hummer_homing_missile_y,
-                    ),
-                ),
-                Weapon::Gm => (
-                    self.g_hardpoint_hummer_guided_missile,
-                    Vec2f::new(
-                        self.g_hardpoint_hummer_guided_missile_x,
-                        self.g_hardpoint_hummer_guided_missile_y,
-                    ),
-                ),
-                Weapon::Bfg => (
-                    self.g_hardpoint_hummer_bfg,
-                    Vec2f::new(self.g_hardpoint_hummer_bfg_x, self.g_hardpoint_hummer_bfg_y),
-                ),
-            },
+        let stats = self.g_hardpoints[veh_type as usize][weapon as usize];
+        (stats.hardpoint, Vec2f::new(stats.x, stats.y))
+    }
+
+    pub fn g_vehicle_hp(&self, veh_type: VehicleType) -> f64 {
+        self.g_armor * self.g_vehicle(veh_type).armor_scale
+    }
+
+    pub fn g_weapon_movement_stats(&self) -> MovementStats {
+        MovementStats {
+            accel_backward: 0.0,
+            accel_forward: self.g_guided_missile_accel_forward,
+            friction_const: self.g_guided_missile_friction_const,
+            friction_linear: self.g_guided_missile_friction_linear,
+            speed_max: self.g_guided_missile_speed_max,
+            steering_car: 0.0,
+            turn_effectiveness: self.g_guided_missile_turn_effectiveness,
+            turn_rate_friction_const: self.g_guided_missile_turn_rate_friction_const,
+            turn_rate_friction_linear: self.g_guided_missile_turn_rate_friction_linear,
+            turn_rate_increase: self.g_guided_missile_turn_rate_increase,
+            turn_rate_max: self.g_guided_missile_turn_rate_max,
         }
     }
 
-    pub fn g_vehicle_hitbox(&self, veh_type: VehicleType) -> Hitbox {
-        match veh_type {
-            VehicleType::Tank => Hitbox {
-                mins: Vec2f::new(self.g_tank_mins_x, self.g_tank_mins_y),
-                maxs: Vec2f::new(self.g_tank_maxs_x, self.g_tank_maxs_y),
-            },
-            VehicleType::Hovercraft => Hitbox {
-                mins: Vec2f::new(self.g_hovercraft_mins_x, self.g_hovercraft_mins_y),
-                maxs: Vec2f::new(self.g_hovercraft_maxs_x, self.g_hovercraft_maxs_y),
-            },
-            VehicleType::Hummer => Hitbox {
-                mins: Vec2f::new(self.g_hummer_mins_x, self.g_hummer_mins_y),
-                maxs: Vec2f::new(self.g_hummer_maxs_x, self.g_hummer_maxs_y),
-            },
+    /// Names of all cvars including stats like `g_rockets.speed`.
+    pub fn names() -> Vec<String> {
+        // Fields which neither `SetGet` nor `get_value` know about are not cvars (e.g. binds or stat tables).
+        let cvars = Cvars::default();
+        let mut names: Vec<_> = Self::FIELDS
+            .iter()
+            .filter(|name| cvars.get_value(name).is_ok())
+            .map(|&name| name.to_owned())
+            .collect();
+        for weapon_name in WEAPON_NAMES {
+            for field in WeaponStats::FIELDS {
+                names.push(format!("g_{weapon_name}.{field}"));
+            }
         }
+        for vehicle_name in VEHICLE_NAMES {
+            for field in VehicleStats::FIELDS {
+                names.push(format!("g_{vehicle_name}.{field}"));
+            }
+            for weapon_name in WEAPON_NAMES {
+                for field in HardpointStats::FIELDS {
+                    names.push(format!("g_{vehicle_name}.{weapon_name}.{field}"));
+                }
+            }
+        }
+        names
     }
 
-    pub fn g_vehicle_hp(&self, veh_type: VehicleType) -> f64 {
-        let scale = match veh_type {
-            VehicleType::Tank => self.g_tank_armor_scale,
-            VehicleType::Hovercraft => self.g_hovercraft_armor_scale,
-            VehicleType::Hummer => self.g_hummer_armor_scale,
+    /// Like `get_string` but also finds stats like `g_rockets.speed`.
+    pub fn get_value(&self, cvar_name: &str) -> Result<String, String> {
+        if cvar_name == "sv_map_rotation" {
+            return Ok(self.sv_map_rotation.to_string());
+        }
+        let value = match StatPath::parse(cvar_name) {
+            None => return self.get_string(cvar_name),
+            Some(StatPath::Weapon(w, field)) => self.g_weapons[w].get_string(field),
+            Some(StatPath::Vehicle(v, field)) => self.g_vehicles[v].get_string(field),
+            Some(StatPath::Hardpoint(v, w, field)) => self.g_hardpoints[v][w].get_string(field),
         };
-        self.g_armor * scale
+        value.ok_or_else(|| format!("Cvar named {} not found", cvar_name))
     }
 
-    pub fn g_vehicle_movement_stats(&self, veh_type: VehicleType) -> MovementStats {
-        match veh_type {
-            VehicleType::Tank => MovementStats {
-                accel_backward: self.g_tank_accel_backward,
-                accel_forward: self.g_tank_accel_forward,
-                friction_const: self.g_tank_friction_const,
-                friction_linear: self.g_tank_friction_linear,
-                speed_max: self.g_tank_speed_max,
-                steering_car: self.g_tank_steering_car,
-                turn_effectiveness: self.g_tank_turn_effectiveness,
-                turn_rate_friction_const: self.g_tank_turn_rate_friction_const,
-                turn_rate_friction_linear: self.g_tank_turn_rate_friction_linear,
-                turn_rate_increase: self.g_tank_turn_rate_increase,
-                turn_rate_max: self.g_tank_turn_rate_max,
+    /// Like `set_str` but also finds stats like `g_rockets.speed`
+    /// and rejects values outside the cvar's `Bounds`.
+    pub fn set_value(&mut self, cvar_name: &str, str_value: &str) -> Result<(), String> {
+        // Check the cvar exists first so typos don't get reported as bad values.
+        self.get_value(cvar_name)?;
+        if let Ok(value) = str_value.parse::<f64>() {
+            let bounds = Bounds::of(cvar_name);
+            if value.is_nan() || (value.is_infinite() && bounds != Some(Bounds::Limit)) {
+                return Err(format!("{cvar_name} must be a finite number"));
+            }
+            if let Some(bounds) = bounds {
+                bounds.check(cvar_name, value)?;
+            }
+        }
+
+        if cvar_name == "sv_map_rotation" {
+            // Infallible
+            self.sv_map_rotation = str_value.parse().unwrap();
+            return Ok(());
+        }
+        let res = match StatPath::parse(cvar_name) {
+            None => return self.set_str(cvar_name, str_value),
+            Some(StatPath::Weapon(w, field)) => self.g_weapons[w].set_str(field, str_value),
+            Some(StatPath::Vehicle(v, field)) => self.g_vehicles[v].set_str(field, str_value),
+            Some(StatPath::Hardpoint(v, w, field)) => {
+                self.g_hardpoints[v][w].set_str(field, str_value)
+            }
+        };
+        res.unwrap_or_else(|| Err(format!("Cvar named {} not found", cvar_name)))
+    }
+}
+
+/// How a cvar may be changed.
+///
+/// Determined by the cvar's prefix so new cvars automatically get the right flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CvarFlags {
+    /// Changes gameplay or gives an advantage - can only be changed with `sv_cheats` enabled
+    pub cheat: bool,
+    /// Owned by the server and sent to clients
+    ///
+    /// LATER Enforce once there's networking - clients can't change these.
+    pub replicated: bool,
+    /// Only affects the local client - can always be changed
+    pub client: bool,
+}
+
+impl CvarFlags {
+    pub fn of(cvar_name: &str) -> Self {
+        let prefix = cvar_name.split('_').next().unwrap();
+        match prefix {
+            "cl" | "con" | "hud" | "r" => CvarFlags {
+                client: true,
+                ..Self::default()
+            },
+            "d" => CvarFlags {
+                cheat: true,
+                ..Self::default()
             },
-            VehicleType::Hovercraft => MovementStats {
-                accel_backward: self.g_hovercraft_accel_backward,
-                accel_forward: self.g_hovercraft_accel_forward,
-                friction_const: self.g_hovercraft_friction_const,
-                friction_linear: self.g_hovercraft_friction_linear,
-                speed_max: self.g_hovercraft_speed_max,
-                steering_car: self.g_hovercraft_steering_car,
-                turn_effectiveness: self.g_hovercraft_turn_effectiveness,
-                turn_rate_friction_const: self.g_hovercraft_turn_rate_friction_const,
-                turn_rate_friction_linear: self.g_hovercraft_turn_rate_friction_linear,
-                turn_rate_increase: self.g_hovercraft_turn_rate_increase,
-                turn_rate_max: self.g_hovercraft_turn_rate_max,
+            "sv" => CvarFlags {
+                replicated: true,
+                ..Self::default()
             },
-            VehicleType::Hummer => MovementStats {
-                accel_backward: self.g_hummer_accel_backward,
-                accel_forward: self.g_hummer_accel_forward,
-                friction_const: self.g_hummer_friction_const,
-                friction_linear: self.g_hummer_friction_linear,
-                speed_max: self.g_hummer_speed_max,
-                steering_car: self.g_hummer_steering_car,
-                turn_effectiveness: self.g_hummer_turn_effectiveness,
-                turn_rate_friction_const: self.g_hummer_turn_rate_friction_const,
-                turn_rate_friction_linear: self.g_hummer_turn_rate_friction_linear,
-                turn_rate_increase: self.g_hummer_turn_rate_increase,
-                turn_rate_max: self.g_hummer_turn_rate_max,
+            // g_ and the few without a prefix like ai and bots_max
+            _ => CvarFlags {
+                cheat: true,
+                replicated: true,
+                ..Self::default()
             },
         }
     }
+}
 
-    /// Where the turret-chassis connection is on the chassis.
-    /// E.g. (0, 0) means the turret rotates around the vehicle's origin.
-    pub fn g_vehicle_turret_offset_chassis(&self, veh_type: VehicleType) -> Vec2f {
-        match veh_type {
-            VehicleType::Tank => Vec2f::new(
-                self.g_tank_turret_offset_chassis_x,
-                self.g_tank_turret_offset_chassis_y,
-            ),
-            VehicleType::Hovercraft => Vec2f::new(
-                self.g_hovercraft_turret_offset_chassis_x,
-                self.g_hovercraft_turret_offset_chassis_y,
-            ),
-            VehicleType::Hummer => Vec2f::new(
-                self.g_hummer_turret_offset_chassis_x,
-                self.g_hummer_turret_offset_chassis_y,
-            ),
-        }
+impl fmt::Display for CvarFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.cheat, "cheat"),
+            (self.replicated, "replicated"),
+            (self.client, "client"),
+        ];
+        let names: Vec<_> = flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join(" "))
     }
+}
 
-    /// Where the turret-chassis connection is on the turret.
-    /// E.g. (0, 0) means the turret rotates around its center.
-    pub fn g_vehicle_turret_offset_turret(&self, veh_type: VehicleType) -> Vec2f {
-        match veh_type {
-            VehicleType::Tank => Vec2f::new(
-                self.g_tank_turret_offset_turret_x,
-                self.g_tank_turret_offset_turret_y,
-            ),
-            VehicleType::Hovercraft => Vec2f::new(
-                self.g_hovercraft_turret_offset_turret_x,
-                self.g_hovercraft_turret_offset_turret_y,
-            ),
-            VehicleType::Hummer => Vec2f::new(
-                self.g_hummer_turret_offset_turret_x,
-                self.g_hummer_turret_offset_turret_y,
-            ),
+/// Limits of `cl_zoom`.
+pub const ZOOM_MIN: f64 = 0.1;
+pub const ZOOM_MAX: f64 = 10.0;
+
+/// Allowed values of a numeric cvar.
+///
+/// All numeric cvars also have to be finite except `Limit`s.
+/// Cvars without bounds can have any value which doesn't crash the game,
+/// even if it makes it unplayable (e.g. negative speeds).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bounds {
+    /// More than zero, e.g. because it's used as a divisor
+    Positive,
+    /// Zero or more
+    NonNegative,
+    /// Between min and max (inclusive)
+    Range(f64, f64),
+    /// Zero or more, `inf` means no limit
+    Limit,
+}
+
+impl Bounds {
+    pub fn of(cvar_name: &str) -> Option<Self> {
+        // Stats are the same for all weapons / vehicles.
+        if let Some((_, field)) = cvar_name.rsplit_once('.') {
+            return match field {
+                "reload_ammo" | "armor_scale" => Some(Bounds::Positive),
+                "explosion_radius" | "refire" | "reload_time" | "steering_car" => {
+                    Some(Bounds::NonNegative)
+                }
+                "speed_max" | "turn_rate_max" => Some(Bounds::Limit),
+                _ => None,
+            };
+        }
+        match cvar_name {
+            "cl_spawn_indicator_animation_time"
+            | "cl_zoom_auto_min"
+            | "cl_zoom_auto_tiles"
+            | "d_fps_period"
+            | "g_armor"
+            | "hud_binds_font_size"
+            | "r_explosion_duration"
+            | "sv_map_vote_options"
+            | "sv_tickrate_max_catch_up"
+            | "sv_tickrate_max_ticks" => Some(Bounds::Positive),
+            "cl_camera_look_ahead_speed"
+            | "cl_zoom_speed"
+            | "d_speed"
+            | "g_bfg_radius"
+            | "g_hitcircle_radius"
+            | "g_respawn_delay"
+            | "g_self_destruct_radius"
+            | "sv_map_vote_time" => Some(Bounds::NonNegative),
+            "g_guided_missile_speed_max" | "g_guided_missile_turn_rate_max" => Some(Bounds::Limit),
+            "cl_gamepad_deadzone"
+            | "cl_gamepad_trigger_threshold"
+            | "con_height_fraction"
+            | "sv_mapgen_wall_density" => Some(Bounds::Range(0.0, 1.0)),
+            "cl_zoom" => Some(Bounds::Range(ZOOM_MIN, ZOOM_MAX)),
+            // Less than 1 would swap zooming in and out.
+            "cl_zoom_step" => Some(Bounds::Range(1.0, ZOOM_MAX)),
+            // Too high would make the server spend all its time simulating.
+            "sv_tickrate_fixed_fps" => Some(Bounds::Range(1.0, 1000.0)),
+            _ => None,
         }
     }
 
-    pub fn g_weapon_damage_direct(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => self.g_machine_gun_damage,
-            Weapon::Rail => self.g_railgun_damage,
-            Weapon::Cb => self.g_cluster_bomb_damage_direct,
-            Weapon::Rockets => self.g_rockets_damage_direct,
-            Weapon::Hm => self.g_homing_missile_damage_direct,
-            Weapon::Gm => self.g_guided_missile_damage_direct,
-            Weapon::Bfg => self.g_bfg_damage_direct,
+    /// The most extreme allowed values.
+    pub fn limits(self) -> Vec<f64> {
+        match self {
+            Bounds::Positive => vec![f64::MIN_POSITIVE],
+            Bounds::NonNegative => vec![0.0],
+            Bounds::Range(min, max) => vec![min, max],
+            Bounds::Limit => vec![0.0, f64::INFINITY],
         }
     }
 
-    pub fn g_weapon_explosion_damage(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => 0.0,
-            Weapon::Rail => 0.0,
-            Weapon::Cb => self.g_cluster_bomb_explosion_damage,
-            Weapon::Rockets => self.g_rockets_explosion_damage,
-            Weapon::Hm => self.g_homing_missile_explosion_damage,
-            Weapon::Gm => self.g_guided_missile_explosion_damage,
-            Weapon::Bfg => self.g_bfg_explosion_damage,
+    fn check(self, cvar_name: &str, value: f64) -> Result<(), String> {
+        let ok = match self {
+            Bounds::Positive => value > 0.0,
+            Bounds::NonNegative => value >= 0.0,
+            Bounds::Range(min, max) => (min..=max).contains(&value),
+            Bounds::Limit => value >= 0.0,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("{cvar_name} must be {self}"))
         }
     }
+}
 
-    pub fn g_weapon_explosion_radius(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => 0.0,
-            Weapon::Rail => 0.0,
-            Weapon::Cb => self.g_cluster_bomb_explosion_radius,
-            Weapon::Rockets => self.g_rockets_explosion_radius,
-            Weapon::Hm => self.g_homing_missile_explosion_radius,
-            Weapon::Gm => self.g_guided_missile_explosion_radius,
-            Weapon::Bfg => self.g_bfg_explosion_radius,
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bounds::Positive => write!(f, "positive"),
+            Bounds::NonNegative => write!(f, "zero or more"),
+            Bounds::Range(min, max) => write!(f, "between {min} and {max}"),
+            Bounds::Limit => write!(f, "zero or more (inf for no limit)"),
         }
     }
+}
+
+/// Names used in stat cvars (e.g. `g_rockets.speed`) in the same order as `Weapon`.
+pub const WEAPON_NAMES: [&str; WEAPS_CNT as usize] = [
+    "machine_gun",
+    "railgun",
+    "cluster_bomb",
+    "rockets",
+    "homing_missile",
+    "guided_missile",
+    "bfg",
+];
+
+/// Names used in stat cvars (e.g. `g_tank.speed_max`) in the same order as `VehicleType`.
+pub const VEHICLE_NAMES: [&str; VEHICLES_CNT] = ["tank", "hovercraft", "hummer"];
+
+/// Which stat record a dotted cvar name refers to and the field name inside it.
+#[derive(Debug, Clone, Copy)]
+enum StatPath<'a> {
+    Weapon(usize, &'a str),
+    Vehicle(usize, &'a str),
+    Hardpoint(usize, usize, &'a str),
+}
 
-    pub fn g_weapon_explosion_scale(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => 0.0,
-            Weapon::Rail => 0.0,
-            Weapon::Cb => self.g_cluster_bomb_explosion_scale,
-            Weapon::Rockets => self.g_rockets_explosion_scale,
-            Weapon::Hm => self.g_homing_missile_explosion_scale,
-            Weapon::Gm => self.g_guided_missile_explosion_scale,
-            Weapon::Bfg => self.g_bfg_explosion_scale,
+impl<'a> StatPath<'a> {
+    fn parse(cvar_name: &'a str) -> Option<Self> {
+        let (record, field) = cvar_name.strip_prefix("g_")?.split_once('.')?;
+        if let Some(w) = WEAPON_NAMES.iter().position(|&name| name == record) {
+            return Some(StatPath::Weapon(w, field));
+        }
+        let v = VEHICLE_NAMES.iter().position(|&name| name == record)?;
+        if let Some((weapon_name, field)) = field.split_once('.') {
+            let w = WEAPON_NAMES.iter().position(|&name| name == weapon_name)?;
+            Some(StatPath::Hardpoint(v, w, field))
+        } else {
+            Some(StatPath::Vehicle(v, field))
         }
     }
+}
 
-    pub fn g_weapon_movement_stats(&self) -> MovementStats {
-        MovementStats {
-            accel_backward: 0.0,
-            accel_forward: self.g_guided_missile_accel_forward,
-            friction_const: self.g_guided_missile_friction_const,
-            friction_linear: self.g_guided_missile_friction_linear,
-            speed_max: self.g_guided_missile_speed_max,
-            steering_car: 0.0,
-            turn_effectiveness: self.g_guided_missile_turn_effectiveness,
-            turn_rate_friction_const: self.g_guided_missile_turn_rate_friction_const,
-            turn_rate_friction_linear: self.g_guided_missile_turn_rate_friction_linear,
-            turn_rate_increase: self.g_guided_missile_turn_rate_increase,
-            turn_rate_max: self.g_guided_missile_turn_rate_max,
+/// Declare a struct whose fields can be read and set by name like cvars.
+///
+/// This is what allows addressing stats as e.g. `g_rockets.speed`
+/// without writing out every combination of weapon/vehicle and field.
+macro_rules! stat_record {
+    (
+        $( #[$meta:meta] )*
+        pub struct $name:ident {
+            $( $( #[$field_meta:meta] )* pub $field:ident: $ty:ty, )*
+        }
+    ) => {
+        $( #[$meta] )*
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name {
+            $( $( #[$field_meta] )* pub $field: $ty, )*
         }
-    }
 
-    pub fn g_weapon_refire(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => self.g_machine_gun_refire,
-            Weapon::Rail => 0.0,
-            Weapon::Cb => 0.0,
-            Weapon::Rockets => self.g_rockets_refire,
-            Weapon::Hm => 0.0,
-            Weapon::Gm => 0.0,
-            Weapon::Bfg => 0.0,
+        impl $name {
+            pub const FIELDS: &'static [&'static str] = &[ $( stringify!($field), )* ];
+
+            pub fn get_string(&self, field: &str) -> Option<String> {
+                match field {
+                    $( stringify!($field) => Some(self.$field.to_string()), )*
+                    _ => None,
+                }
+            }
+
+            /// Returns `None` if there's no such field.
+            pub fn set_str(&mut self, field: &str, str_value: &str) -> Option<Result<(), String>> {
+                match field {
+                    $( stringify!($field) => Some(match str_value.parse::<$ty>() {
+                        Ok(value) => {
+                            self.$field = value;
+                            Ok(())
+                        }
+                        Err(err) => Err(format!(
+                            "failed to parse {} as type {}: {}",
+                            str_value,
+                            stringify!($ty),
+                            err,
+                        )),
+                    }), )*
+                    _ => None,
+                }
+            }
         }
+    };
+}
+
+stat_record! {
+    /// Stats every weapon has, unused ones are 0.
+    ///
+    /// Stats specific to one weapon are normal cvars like `g_cluster_bomb_count`.
+    pub struct WeaponStats {
+        pub damage_direct: f64,
+        pub explosion_damage: f64,
+        pub explosion_radius: f64,
+        pub explosion_scale: f64,
+        /// Delay between shots without reloading
+        pub refire: f64,
+        pub reload_ammo: u32,
+        pub reload_time: f64,
+        /// Initial speed of the projectile
+        pub speed: f64,
+        /// How much of the vehicle's velocity is added to the projectile
+        pub vehicle_velocity_factor: f64,
     }
+}
+
+stat_record! {
+    pub struct VehicleStats {
+        /// Multiplier of `g_armor`
+        pub armor_scale: f64,
+        pub accel_backward: f64,
+        pub accel_forward: f64,
+        pub friction_const: f64,
+        pub friction_linear: f64,
+        pub speed_max: f64,
+        pub steering_car: f64,
+        pub turn_effectiveness: f64,
+        pub turn_rate_friction_const: f64,
+        pub turn_rate_friction_linear: f64,
+        pub turn_rate_increase: f64,
+        pub turn_rate_max: f64,
+        pub mins_x: f64,
+        pub mins_y: f64,
+        pub maxs_x: f64,
+        pub maxs_y: f64,
+        pub turret_offset_chassis_x: f64,
+        pub turret_offset_chassis_y: f64,
+        pub turret_offset_turret_x: f64,
+        pub turret_offset_turret_y: f64,
+    }
+}
 
-    pub fn g_weapon_reload_ammo(&self, weapon: Weapon) -> u32 {
-        match weapon {
-            Weapon::Mg => self.g_machine_gun_reload_ammo,
-            Weapon::Rail => self.g_railgun_reload_ammo,
-            Weapon::Cb => self.g_cluster_bomb_reload_ammo,
-            Weapon::Rockets => self.g_rockets_reload_ammo,
-            Weapon::Hm => self.g_homing_missile_reload_ammo,
-            Weapon::Gm => self.g_guided_missile_reload_ammo,
-            Weapon::Bfg => self.g_bfg_reload_ammo,
+impl VehicleStats {
+    pub fn hitbox(&self) -> Hitbox {
+        Hitbox {
+            mins: Vec2f::new(self.mins_x, self.mins_y),
+            maxs: Vec2f::new(self.maxs_x, self.maxs_y),
         }
     }
 
-    pub fn g_weapon_reload_time(&self, weapon: Weapon) -> f64 {
-        match weapon {
-            Weapon::Mg => self.g_machine_gun_reload_time,
-            Weapon::Rail => self.g_railgun_reload_time,
-            Weapon::Cb => self.g_cluster_bomb_reload_time,
-            Weapon::Rockets => self.g_rockets_reload_time,
-            Weapon::Hm => self.g_homing_missile_reload_time,
-            Weapon::Gm => self.g_guided_missile_reload_time,
-            Weapon::Bfg => self.g_bfg_reload_time,
+    pub fn movement_stats(&self) -> MovementStats {
+        MovementStats {
+            accel_backward: self.accel_backward,
+            accel_forward: self.accel_forward,
+            friction_const: self.friction_const,
+            friction_linear: self.friction_linear,
+            speed_max: self.speed_max,
+            steering_car: self.steering_car,
+            turn_effectiveness: self.turn_effectiveness,
+            turn_rate_friction_const: self.turn_rate_friction_const,
+            turn_rate_friction_linear: self.turn_rate_friction_linear,
+            turn_rate_increase: self.turn_rate_increase,
+            turn_rate_max: self.turn_rate_max,
         }
     }
+
+    /// Where the turret-chassis connection is on the chassis.
+    /// E.g. (0, 0) means the turret rotates around the vehicle's origin.
+    pub fn turret_offset_chassis(&self) -> Vec2f {
+        Vec2f::new(self.turret_offset_chassis_x, self.turret_offset_chassis_y)
+    }
+
+    /// Where the turret-chassis connection is on the turret.
+    /// E.g. (0, 0) means the turret rotates around its center.
+    pub fn turret_offset_turret(&self) -> Vec2f {
+        Vec2f::new(self.turret_offset_turret_x, self.turret_offset_turret_y)
+    }
+}
+
+stat_record! {
+    /// Where a vehicle has a weapon.
+    pub struct HardpointStats {
+        pub hardpoint: Hardpoint,
+        /// Relative to the center of the part given by `hardpoint`
+        pub x: f64,
+        pub y: f64,
+    }
 }
 
 impl Default for Cvars {
@@ -811,11 +881,30 @@ impl Default for Cvars {
 
             bots_max: 20,
 
+            cl_binds: Binds::new_players(),
+
+            cl_camera_look_ahead: LookAhead::Off,
+            cl_camera_look_ahead_distance: 150.0,
+            cl_camera_look_ahead_time: 0.5,
+            cl_camera_look_ahead_speed: 3.0,
+
             cl_cluster_bomb_size: 1.5,
 
+            cl_editor_scroll_speed: 1000.0,
+
+            cl_gamepad_deadzone: 0.25,
+            cl_gamepad_player1: 1,
+            cl_gamepad_player2: 2,
+            cl_gamepad_player3: 3,
+            cl_gamepad_player4: 4,
+            cl_gamepad_trigger_threshold: 0.5,
+            cl_gamepads: true,
+
             cl_machine_gun_trail_length: 10.0,
             cl_machine_gun_trail_thickness: 1.5,
 
+            cl_mouse_aim: false,
+
             cl_railgun_trail_thickness: 1.5,
             cl_railgun_trail_duration: 0.05,
 
@@ -826,6 +915,13 @@ impl Default for Cvars {
             cl_spawn_indicator_square_side_end: 40.0,
             cl_spawn_indicator_thickness: 2.0,
 
+            cl_zoom: 1.0,
+            cl_zoom_auto: true,
+            cl_zoom_auto_min: 0.5,
+            cl_zoom_auto_tiles: 100.0,
+            cl_zoom_speed: 8.0,
+            cl_zoom_step: 1.25,
+
             con_background_alpha: 0.8,
             con_prompt_group_x: 16.0,
             con_prompt_group_y_offset: 26.0,
@@ -862,238 +958,359 @@ impl Default for Cvars {
 
             g_armor: 50.0,
 
+            g_weapons: [
+                // machine_gun
+                WeaponStats {
+                    damage_direct: 2.5, // exact from orig RW
+                    explosion_damage: 0.0,
+                    explosion_radius: 0.0,
+                    explosion_scale: 0.0,
+                    refire: 0.050,
+                    reload_ammo: 50,
+                    reload_time: 1.0,
+                    speed: 1000.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+                // railgun
+                WeaponStats {
+                    damage_direct: 47.0, // exact from orig RW
+                    explosion_damage: 0.0,
+                    explosion_radius: 0.0,
+                    explosion_scale: 0.0,
+                    refire: 0.0,
+                    reload_ammo: 1,
+                    reload_time: 1.0,
+                    speed: 2500.0,
+                    vehicle_velocity_factor: 0.0,
+                },
+                // cluster_bomb
+                WeaponStats {
+                    damage_direct: 0.0, // best guess - same as rockets
+                    explosion_damage: 25.0,
+                    explosion_radius: 20.0,
+                    explosion_scale: 0.5,
+                    refire: 0.0,
+                    reload_ammo: 1,
+                    reload_time: 1.5,
+                    speed: 400.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+                // rockets
+                WeaponStats {
+                    damage_direct: 25.0, // pretty sure from orig RW testing
+                    explosion_damage: 0.0,
+                    explosion_radius: 20.0,
+                    explosion_scale: 0.5,
+                    refire: 0.200,
+                    reload_ammo: 6,
+                    reload_time: 1.5,
+                    speed: 600.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+                // homing_missile
+                WeaponStats {
+                    damage_direct: 0.0,
+                    explosion_damage: 56.0, // assumed same as GM
+                    explosion_radius: 40.0,
+                    explosion_scale: 1.0,
+                    refire: 0.0,
+                    reload_ammo: 1,
+                    reload_time: 1.5,
+                    speed: 360.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+                // guided_missile
+                WeaponStats {
+                    damage_direct: 0.0,
+                    explosion_damage: 56.0, // exact from orig RW
+                    explosion_radius: 40.0,
+                    explosion_scale: 1.0,
+                    refire: 0.0,
+                    reload_ammo: 1,
+                    reload_time: 1.5,
+                    speed: 100.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+                // bfg
+                WeaponStats {
+                    damage_direct: 0.0,
+                    explosion_damage: 100.0, // pretty sure from orig RW testing
+                    explosion_radius: 40.0,
+                    explosion_scale: 1.0,
+                    refire: 0.0,
+                    reload_ammo: 1,
+                    reload_time: 2.5,
+                    speed: 150.0,
+                    vehicle_velocity_factor: 1.0,
+                },
+            ],
+            g_vehicles: [
+                // tank
+                VehicleStats {
+                    armor_scale: 1.0,
+                    accel_backward: 550.0,
+                    accel_forward: 550.0,
+                    friction_const: 50.0,
+                    friction_linear: 0.9,
+                    speed_max: f64::INFINITY,
+                    steering_car: 0.0,
+                    turn_effectiveness: 1.0,
+                    turn_rate_friction_const: 0.05,
+                    turn_rate_friction_linear: 0.96,
+                    turn_rate_increase: 8.0,
+                    turn_rate_max: f64::INFINITY,
+                    mins_x: -19.0,
+                    mins_y: -12.0,
+                    maxs_x: 19.0,
+                    maxs_y: 12.0,
+                    turret_offset_chassis_x: -5.0,
+                    turret_offset_chassis_y: 0.0,
+                    turret_offset_turret_x: -14.0,
+                    turret_offset_turret_y: 0.0,
+                },
+                // hovercraft
+                VehicleStats {
+                    armor_scale: 0.65,
+                    accel_backward: 400.0,
+                    accel_forward: 400.0,
+                    friction_const: 0.0,
+                    friction_linear: 0.6,
+                    speed_max: f64::INFINITY,
+                    steering_car: 0.0,
+                    turn_effectiveness: 0.0,
+                    turn_rate_friction_const: 0.03,
+                    turn_rate_friction_linear: 0.92,
+                    turn_rate_increase: 10.0,
+                    turn_rate_max: f64::INFINITY,
+                    mins_x: -22.0,
+                    mins_y: -14.0,
+                    maxs_x: 22.0,
+                    maxs_y: 14.0,
+                    turret_offset_chassis_x: -9.0,
+                    turret_offset_chassis_y: 5.0,
+                    turret_offset_turret_x: -8.0,
+                    turret_offset_turret_y: 0.0,
+                },
+                // hummer
+                VehicleStats {
+                    armor_scale: 0.625,
+                    accel_backward: 600.0,
+                    accel_forward: 600.0,
+                    friction_const: 11.0,
+                    friction_linear: 0.8,
+                    speed_max: f64::INFINITY,
+                    steering_car: 200.0,
+                    turn_effectiveness: 1.0,
+                    turn_rate_friction_const: 0.04,
+                    turn_rate_friction_linear: 0.97,
+                    turn_rate_increase: 18.0,
+                    turn_rate_max: f64::INFINITY,
+                    mins_x: -20.0,
+                    mins_y: -9.0,
+                    maxs_x: 20.0,
+                    maxs_y: 9.0,
+                    turret_offset_chassis_x: -12.0,
+                    turret_offset_chassis_y: 0.0,
+                    turret_offset_turret_x: 0.0,
+                    turret_offset_turret_y: 0.0,
+                },
+            ],
+            g_hardpoints: [
+                // tank
+                [
+                    // machine_gun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 12.0,
+                        y: -5.0,
+                    },
+                    // railgun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 35.0,
+                        y: 0.0,
+                    },
+                    // cluster_bomb
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 35.0,
+                        y: 0.0,
+                    },
+                    // rockets
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 35.0,
+                        y: 0.0,
+                    },
+                    // homing_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -14.0,
+                    },
+                    // guided_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -14.0,
+                    },
+                    // bfg
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 35.0,
+                        y: 0.0,
+                    },
+                ],
+                // hovercraft
+                [
+                    // machine_gun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 19.0,
+                        y: 0.0,
+                    },
+                    // railgun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 19.0,
+                        y: 0.0,
+                    },
+                    // cluster_bomb
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 19.0,
+                        y: 0.0,
+                    },
+                    // rockets
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 19.0,
+                        y: 0.0,
+                    },
+                    // homing_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -16.0,
+                    },
+                    // guided_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -16.0,
+                    },
+                    // bfg
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 19.0,
+                        y: 0.0,
+                    },
+                ],
+                // hummer
+                [
+                    // machine_gun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 10.0,
+                        y: 9.0,
+                    },
+                    // railgun
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 10.0,
+                        y: 9.0,
+                    },
+                    // cluster_bomb
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 0.0,
+                        y: 0.0,
+                    },
+                    // rockets
+                    HardpointStats {
+                        hardpoint: Hardpoint::Turret,
+                        x: 0.0,
+                        y: 0.0,
+                    },
+                    // homing_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -10.0,
+                    },
+                    // guided_missile
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 0.0,
+                        y: -10.0,
+                    },
+                    // bfg
+                    HardpointStats {
+                        hardpoint: Hardpoint::Chassis,
+                        x: 10.0,
+                        y: 9.0,
+                    },
+                ],
+            ],
+
             g_bfg_beam_damage_per_sec: 25.0,
             g_bfg_beam_range: 125.0,
-            g_bfg_damage_direct: 0.0,
-            g_bfg_explosion_damage: 100.0, // pretty sure from orig RW testing
-            g_bfg_explosion_radius: 40.0,
-            g_bfg_explosion_scale: 1.0,
             g_bfg_radius: 4.0,
-            g_bfg_reload_ammo: 1,
-            g_bfg_reload_time: 2.5,
-            g_bfg_speed: 150.0,
-            g_bfg_vehicle_velocity_factor: 1.0,
 
             g_cluster_bomb_count: 40,
-            g_cluster_bomb_damage_direct: 0.0, // best guess - same as rockets
-            g_cluster_bomb_explosion_damage: 25.0,
-            g_cluster_bomb_explosion_radius: 20.0,
-            g_cluster_bomb_explosion_scale: 0.5,
-            g_cluster_bomb_reload_ammo: 1,
-            g_cluster_bomb_reload_time: 1.5,
             g_cluster_bomb_shadow_alpha: 1.0,
             g_cluster_bomb_shadow_x: 2.0,
             g_cluster_bomb_shadow_y: 2.0,
-            g_cluster_bomb_speed: 400.0,
             g_cluster_bomb_speed_spread_forward: 50.0,
             g_cluster_bomb_speed_spread_gaussian: true,
             g_cluster_bomb_speed_spread_sideways: 50.0,
             g_cluster_bomb_time: 0.8,
             g_cluster_bomb_time_spread: 0.2,
-            g_cluster_bomb_vehicle_velocity_factor: 1.0,
 
             g_ffa_score_kill: 1,
             g_ffa_score_death: -1,
 
-            g_homing_missile_damage_direct: 0.0,
-            g_homing_missile_explosion_damage: 56.0, // assumed same as GM
-            g_homing_missile_explosion_radius: 40.0,
-            g_homing_missile_explosion_scale: 1.0,
-            g_homing_missile_reload_ammo: 1,
-            g_homing_missile_reload_time: 1.5,
-            g_homing_missile_speed_initial: 360.0,
-            g_homing_missile_vehicle_velocity_factor: 1.0,
-
             g_machine_gun_angle_spread: 0.015,
-            g_machine_gun_damage: 2.5, // exact from orig RW
-            g_machine_gun_refire: 0.050,
-            g_machine_gun_reload_ammo: 50,
-            g_machine_gun_reload_time: 1.0,
-            g_machine_gun_speed: 1000.0,
-            g_machine_gun_vehicle_velocity_factor: 1.0,
 
             g_guided_missile_accel_forward: 2000.0,
-            g_guided_missile_damage_direct: 0.0,
-            g_guided_missile_explosion_damage: 56.0, // exact from orig RW
-            g_guided_missile_explosion_radius: 40.0,
-            g_guided_missile_explosion_scale: 1.0,
             g_guided_missile_friction_const: 0.0,
             g_guided_missile_friction_linear: 0.99,
-            g_guided_missile_reload_ammo: 1,
-            g_guided_missile_reload_time: 1.5,
-            g_guided_missile_speed_initial: 100.0,
             g_guided_missile_speed_max: f64::INFINITY,
             g_guided_missile_turn_effectiveness: 1.0,
             g_guided_missile_turn_rate_friction_const: 0.10,
             g_guided_missile_turn_rate_friction_linear: 0.995,
             g_guided_missile_turn_rate_increase: 30.0,
             g_guided_missile_turn_rate_max: f64::INFINITY,
-            g_guided_missile_vehicle_velocity_factor: 1.0,
-
-            g_hardpoint_hovercraft_machine_gun: Hardpoint::Turret,
-            g_hardpoint_hovercraft_machine_gun_x: 19.0,
-            g_hardpoint_hovercraft_machine_gun_y: 0.0,
-            g_hardpoint_hovercraft_railgun: Hardpoint::Turret,
-            g_hardpoint_hovercraft_railgun_x: 19.0,
-            g_hardpoint_hovercraft_railgun_y: 0.0,
-            g_hardpoint_hovercraft_cluster_bomb: Hardpoint::Turret,
-            g_hardpoint_hovercraft_cluster_bomb_x: 19.0,
-            g_hardpoint_hovercraft_cluster_bomb_y: 0.0,
-            g_hardpoint_hovercraft_rockets: Hardpoint::Turret,
-            g_hardpoint_hovercraft_rockets_x: 19.0,
-            g_hardpoint_hovercraft_rockets_y: 0.0,
-            g_hardpoint_hovercraft_homing_missile: Hardpoint::Chassis,
-            g_hardpoint_hovercraft_homing_missile_x: 0.0,
-            g_hardpoint_hovercraft_homing_missile_y: -16.0,
-            g_hardpoint_hovercraft_guided_missile: Hardpoint::Chassis,
-            g_hardpoint_hovercraft_guided_missile_x: 0.0,
-            g_hardpoint_hovercraft_guided_missile_y: -16.0,
-            g_hardpoint_hovercraft_bfg: Hardpoint::Turret,
-            g_hardpoint_hovercraft_bfg_x: 19.0,
-            g_hardpoint_hovercraft_bfg_y: 0.0,
-
-            g_hardpoint_hummer_machine_gun: Hardpoint::Chassis,
-            g_hardpoint_hummer_machine_gun_x: 10.0,
-            g_hardpoint_hummer_machine_gun_y: 9.0,
-            g_hardpoint_hummer_railgun: Hardpoint::Chassis,
-            g_hardpoint_hummer_railgun_x: 10.0,
-            g_hardpoint_hummer_railgun_y: 9.0,
-            g_hardpoint_hummer_cluster_bomb: Hardpoint::Turret,
-            g_hardpoint_hummer_cluster_bomb_x: 0.0,
-            g_hardpoint_hummer_cluster_bomb_y: 0.0,
-            g_hardpoint_hummer_rockets: Hardpoint::Turret,
-            g_hardpoint_hummer_rockets_x: 0.0,
-            g_hardpoint_hummer_rockets_y: 0.0,
-            g_hardpoint_hummer_homing_missile: Hardpoint::Chassis,
-            g_hardpoint_hummer_homing_missile_x: 0.0,
-            g_hardpoint_hummer_homing_missile_y: -10.0,
-            g_hardpoint_hummer_guided_missile: Hardpoint::Chassis,
-            g_hardpoint_hummer_guided_missile_x: 0.0,
-            g_hardpoint_hummer_guided_missile_y: -10.0,
-            g_hardpoint_hummer_bfg: Hardpoint::Chassis,
-            g_hardpoint_hummer_bfg_x: 10.0,
-            g_hardpoint_hummer_bfg_y: 9.0,
-
-            g_hardpoint_tank_machine_gun: Hardpoint::Turret,
-            g_hardpoint_tank_machine_gun_x: 12.0,
-            g_hardpoint_tank_machine_gun_y: -5.0,
-            g_hardpoint_tank_railgun: Hardpoint::Turret,
-            g_hardpoint_tank_railgun_x: 35.0,
-            g_hardpoint_tank_railgun_y: 0.0,
-            g_hardpoint_tank_cluster_bomb: Hardpoint::Turret,
-            g_hardpoint_tank_cluster_bomb_x: 35.0,
-            g_hardpoint_tank_cluster_bomb_y: 0.0,
-            g_hardpoint_tank_rockets: Hardpoint::Turret,
-            g_hardpoint_tank_rockets_x: 35.0,
-            g_hardpoint_tank_rockets_y: 0.0,
-            g_hardpoint_tank_homing_missile: Hardpoint::Chassis,
-            g_hardpoint_tank_homing_missile_x: 0.0,
-            g_hardpoint_tank_homing_missile_y: -14.0,
-            g_hardpoint_tank_guided_missile: Hardpoint::Chassis,
-            g_hardpoint_tank_guided_missile_x: 0.0,
-            g_hardpoint_tank_guided_missile_y: -14.0,
-            g_hardpoint_tank_bfg: Hardpoint::Turret,
-            g_hardpoint_tank_bfg_x: 35.0,
-            g_hardpoint_tank_bfg_y: 0.0,
 
             g_hitcircle_radius: 24.0,
 
-            g_hovercraft_armor_scale: 0.65,
-            g_hovercraft_accel_backward: 400.0,
-            g_hovercraft_accel_forward: 400.0,
-            g_hovercraft_friction_const: 0.0,
-            g_hovercraft_friction_linear: 0.6,
-            g_hovercraft_maxs_x: 22.0,
-            g_hovercraft_maxs_y: 14.0,
-            g_hovercraft_mins_x: -22.0,
-            g_hovercraft_mins_y: -14.0,
-            g_hovercraft_speed_max: f64::INFINITY,
-            g_hovercraft_steering_car: 0.0,
-            g_hovercraft_turn_effectiveness: 0.0,
-            g_hovercraft_turn_rate_friction_const: 0.03,
-            g_hovercraft_turn_rate_friction_linear: 0.92,
-            g_hovercraft_turn_rate_increase: 10.0,
-            g_hovercraft_turn_rate_max: f64::INFINITY,
-            g_hovercraft_turret_offset_chassis_x: -9.0,
-            g_hovercraft_turret_offset_chassis_y: 5.0,
-            g_hovercraft_turret_offset_turret_x: -8.0,
-            g_hovercraft_turret_offset_turret_y: 0.0,
-
-            g_hummer_armor_scale: 0.625,
-            g_hummer_accel_backward: 600.0,
-            g_hummer_accel_forward: 600.0,
-            g_hummer_friction_const: 11.0,
-            g_hummer_friction_linear: 0.8,
-            g_hummer_maxs_x: 20.0,
-            g_hummer_maxs_y: 9.0,
-            g_hummer_mins_x: -20.0,
-            g_hummer_mins_y: -9.0,
-            g_hummer_speed_max: f64::INFINITY,
-            g_hummer_steering_car: 200.0,
-            g_hummer_turn_effectiveness: 1.0,
-            g_hummer_turn_rate_friction_const: 0.04,
-            g_hummer_turn_rate_friction_linear: 0.97,
-            g_hummer_turn_rate_increase: 18.0,
-            g_hummer_turn_rate_max: f64::INFINITY,
-            g_hummer_turret_offset_chassis_x: -12.0,
-            g_hummer_turret_offset_chassis_y: 0.0,
-            g_hummer_turret_offset_turret_x: 0.0,
-            g_hummer_turret_offset_turret_y: 0.0,
-
-            g_railgun_damage: 47.0, // exact from orig RW
+            g_match_points_limit: 0,
+            g_match_time_limit: 0.0,
+
             g_railgun_push: 300.0,
-            g_railgun_reload_ammo: 1,
-            g_railgun_reload_time: 1.0,
-            g_railgun_speed: 2500.0,
-            g_railgun_vehicle_velocity_factor: 0.0,
 
             // LATER this used to be 2 s, did RW use 2 s?
             g_respawn_delay: 0.5,
 
-            g_rockets_damage_direct: 25.0, // pretty sure from orig RW testing
-            g_rockets_explosion_damage: 0.0,
-            g_rockets_explosion_radius: 20.0,
-            g_rockets_explosion_scale: 0.5,
-            g_rockets_refire: 0.200,
-            g_rockets_reload_ammo: 6,
-            g_rockets_reload_time: 1.5,
-            g_rockets_speed: 600.0,
-            g_rockets_vehicle_velocity_factor: 1.0,
-
             g_self_destruct_damage_center: 150.0,
             g_self_destruct_damage_edge: 0.0,
             g_self_destruct_explosion_scale: 2.0,
             g_self_destruct_radius: 175.0,
 
-            g_tank_armor_scale: 1.0,
-            g_tank_accel_backward: 550.0,
-            g_tank_accel_forward: 550.0,
-            g_tank_friction_const: 50.0,
-            g_tank_friction_linear: 0.9,
-            g_tank_maxs_x: 19.0,
-            g_tank_maxs_y: 12.0,
-            g_tank_mins_x: -19.0,
-            g_tank_mins_y: -12.0,
-            g_tank_speed_max: f64::INFINITY,
-            g_tank_steering_car: 0.0,
-            g_tank_turn_effectiveness: 1.0,
-            g_tank_turn_rate_friction_const: 0.05,
-            g_tank_turn_rate_friction_linear: 0.96,
-            g_tank_turn_rate_increase: 8.0,
-            g_tank_turn_rate_max: f64::INFINITY,
-            g_tank_turret_offset_chassis_x: -5.0,
-            g_tank_turret_offset_chassis_y: 0.0,
-            g_tank_turret_offset_turret_x: -14.0,
-            g_tank_turret_offset_turret_y: 0.0,
-
             g_turret_turn_speed_deg: 120.0,
             g_turret_turn_step_angle_deg: 45.0,
+            g_turret_turn_mode: TurretTurnMode::Queue,
 
             hud_ammo_x: 30.0,
             hud_ammo_y: -30.0,
             hud_ammo_width: 100.0,
             hud_ammo_height: 4.0,
 
+            hud_binds: false,
+            hud_binds_font_size: 16.0,
+
+            hud_crosshair_radius: 8.0,
+
             hud_hp_x: 30.0,
             hud_hp_y: -50.0,
             hud_hp_width: 100.0,
@@ -1152,18 +1369,78 @@ impl Default for Cvars {
             //   because the later explosions were suddenly revealed after the first ones disappeared.
             // - Rockets look better if hitting the same spot.
             r_explosions_reverse_order: false,
+            r_fullscreen: false,
             r_smoothing: false,
             r_splitscreen_gap: 8.0,
+            r_splitscreen_layout: SplitscreenLayout::Auto,
 
             sv_auto_pause_on_minimize: true,
             sv_auto_unpause_on_restore: false,
 
+            sv_cheats: true,
+
+            sv_map_rotation: MapList::default(),
+            sv_map_vote_options: 3,
+            sv_map_vote_time: 15.0,
+
+            sv_mapgen_bases: true,
+            sv_mapgen_biome: Biome::Mixed,
+            sv_mapgen_height: 30,
+            sv_mapgen_players: 2,
+            sv_mapgen_spawns_per_player: 6,
+            sv_mapgen_symmetry: Symmetry::Mirror,
+            sv_mapgen_wall_density: 0.15,
+            sv_mapgen_width: 40,
+
             sv_tickrate_mode: TickrateMode::Synchronized,
             sv_tickrate_fixed_fps: 150.0,
+            sv_tickrate_max_catch_up: 0.25,
+            sv_tickrate_max_ticks: 20,
+            sv_tickrate_overload: TickrateOverload::SlowDown,
         }
     }
 }
 
+/// List of map names (as used with `--map`), written separated by commas.
+///
+/// LATER The console splits on spaces so it can't set names like `Bunkers (2)`, only the command line can.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MapList(pub Vec<String>);
+
+impl MapList {
+    pub fn names(&self) -> Vec<String> {
+        self.0.clone()
+    }
+}
+
+impl FromStr for MapList {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let names = s
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect();
+        Ok(MapList(names))
+    }
+}
+
+impl fmt::Display for MapList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join(", "))
+    }
+}
+
+/// Gameplay presets, see `Cvars::new_rec_war` for the differences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum Balance {
+    RecWars,
+    RecWar,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
 pub enum Hardpoint {
     Chassis,
@@ -1171,7 +1448,7 @@ pub enum Hardpoint {
 }
 
 /// Various options how to handle different physics/gamelogic and rendering framerates.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
 pub enum TickrateMode {
     /// Same FPS as rendering - runs one tick with variable timestep before rendering.
     /// This means simulation always catches up to rendering (wall-clock time) exactly.
@@ -1186,12 +1463,60 @@ pub enum TickrateMode {
     /// resumes from the last full step so it's deterministic. Too small steps are skipped.
     /// This is described by Jonathan Blow here: https://youtu.be/fdAOPHgW7qM?t=7149
     FixedOrSmaller,
-    // There is another option - FixedWithInterpolation:
-    // Instead of running with shorter dt to create the intermediate frame which is thrown away,
-    // we'd wait till the next full simulation frame and interpolate to get the intermediate render frame.
-    // This would however introduce latency.
-    // Also note I believe this would require special handling of events like respawning
-    // to avoid interpolating between death and spawn location.
+    /// Fixed FPS like `Fixed` but instead of rendering the last tick, rendering interpolates
+    /// between the last two ticks using the leftover time. Deterministic and smooth but adds up to one tick of latency.
+    /// Entities which spawned since the previous tick (e.g. respawned vehicles) are not interpolated
+    /// so they don't appear to fly across the map.
+    FixedWithInterpolation,
+}
+
+/// How the screen is divided between local players in splitscreen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
+pub enum SplitscreenLayout {
+    /// Choose the layout which gives each player the most space in their view's shorter direction
+    /// - side by side on wide screens, a grid for 4 players, etc.
+    Auto,
+    /// Side by side
+    Horizontal,
+    /// Above each other
+    Vertical,
+    /// 2x2 for 3 or 4 players
+    Grid,
+}
+
+/// What the camera leads the player's vehicle by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
+pub enum LookAhead {
+    /// Centered on the vehicle
+    Off,
+    /// Toward where the vehicle is moving, further the faster it goes
+    Velocity,
+    /// Toward where the turret is pointing
+    Turret,
+}
+
+/// How turret steps (`g_turret_turn_step_angle_deg`) add up when pressed quickly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
+pub enum TurretTurnMode {
+    /// Every step is added to where the turret is going, like original RecWar.
+    ///
+    /// Quickly pressing left, left, right ends up one step left.
+    Queue,
+    /// Changing direction cancels the queued steps and counts from where the turret currently is.
+    ///
+    /// Quickly pressing left, left, right ends up a bit less than one step right of where it started.
+    Cancel,
+}
+
+/// How to handle running out of `sv_tickrate_max_ticks` - when the server can't keep up
+/// with a fixed tickrate, running more ticks would make the next update take even longer (a death spiral).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, EnumIter)]
+pub enum TickrateOverload {
+    /// Throw away the leftover time - the game skips a bit but stays in sync with wall-clock time.
+    Drop,
+    /// Keep the leftover time (up to `sv_tickrate_max_catch_up`) for the following updates
+    /// - the game runs slower until it catches up.
+    SlowDown,
 }
 
 #[derive(Debug, Clone)]
@@ -1208,3 +1533,85 @@ pub struct MovementStats {
     pub turn_rate_increase: f64,
     pub turn_rate_max: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_stats() {
+        let mut cvars = Cvars::default();
+        assert_eq!(cvars.get_value("g_rockets.speed").unwrap(), "600");
+        cvars.set_value("g_rockets.speed", "700").unwrap();
+        assert_eq!(cvars.g_weapon(Weapon::Rockets).speed, 700.0);
+        cvars
+            .set_value("g_hummer.turn_rate_increase", "20")
+            .unwrap();
+        assert_eq!(
+            cvars.g_vehicle(VehicleType::Hummer).turn_rate_increase,
+            20.0
+        );
+        cvars.set_value("g_tank.bfg.hardpoint", "Chassis").unwrap();
+        cvars.set_value("g_tank.bfg.y", "-3").unwrap();
+        let hardpoint = cvars.g_hardpoint(VehicleType::Tank, Weapon::Bfg);
+        assert_eq!(hardpoint, (Hardpoint::Chassis, Vec2f::new(35.0, -3.0)));
+
+        assert!(cvars.set_value("g_rockets.speed", "fast").is_err());
+        assert!(cvars.set_value("g_rockets.color", "1").is_err());
+        assert!(cvars.set_value("g_laser.speed", "1").is_err());
+        assert!(cvars.set_value("g_tank.laser.x", "1").is_err());
+        assert!(cvars.get_value("g_rockets").is_err());
+    }
+
+    #[test]
+    fn test_cvar_flags() {
+        assert_eq!(
+            CvarFlags::of("g_rockets.speed").to_string(),
+            "cheat replicated"
+        );
+        assert_eq!(CvarFlags::of("bots_max").to_string(), "cheat replicated");
+        assert_eq!(CvarFlags::of("d_speed").to_string(), "cheat");
+        assert_eq!(CvarFlags::of("sv_cheats").to_string(), "replicated");
+        assert_eq!(CvarFlags::of("hud_names").to_string(), "client");
+    }
+
+    #[test]
+    fn test_bounds() {
+        // Defaults of all presets are valid.
+        for balance in Balance::iter() {
+            let defaults = Cvars::new_balance(balance);
+            let mut cvars = defaults.clone();
+            for name in Cvars::names() {
+                let value = defaults.get_value(&name).unwrap();
+                cvars.set_value(&name, &value).unwrap();
+            }
+        }
+
+        let mut cvars = Cvars::default();
+        let err = cvars.set_value("d_speed", "NaN").unwrap_err();
+        assert_eq!(err, "d_speed must be a finite number");
+        assert!(cvars.set_value("g_armor", "inf").is_err());
+        cvars.set_value("g_tank.speed_max", "inf").unwrap();
+        assert!(cvars.set_value("g_tank.speed_max", "-inf").is_err());
+        let err = cvars.set_value("g_hitcircle_radius", "-1").unwrap_err();
+        assert_eq!(err, "g_hitcircle_radius must be zero or more");
+        let err = cvars.set_value("sv_tickrate_fixed_fps", "0").unwrap_err();
+        assert_eq!(err, "sv_tickrate_fixed_fps must be between 1 and 1000");
+        let err = cvars.set_value("g_rockets.reload_ammo", "0").unwrap_err();
+        assert_eq!(err, "g_rockets.reload_ammo must be positive");
+        let err = cvars.set_value("nope", "NaN").unwrap_err();
+        assert!(err.contains("not found"), "{err}");
+        assert_eq!(cvars.d_speed, 1.0);
+        assert_eq!(
+            cvars.sv_tickrate_fixed_fps,
+            Cvars::default().sv_tickrate_fixed_fps
+        );
+
+        cvars.set_value("g_hitcircle_radius", "0").unwrap();
+        cvars.set_value("sv_tickrate_fixed_fps", "1000").unwrap();
+        cvars.set_value("g_rockets.reload_ammo", "1").unwrap();
+        assert_eq!(cvars.g_weapon(Weapon::Rockets).reload_ammo, 1);
+    }
+}
@@ -0,0 +1,159 @@
+//! In-game map editor.
+//!
+//! Paints tiles using the surfaces from the texture list and saves the result
+//! in the original RecWar format so it can be loaded by `map::load_map`.
+//! Spawns and bases are just surfaces with a special `Kind`.
+
+use std::fs;
+
+use macroquad::prelude::*;
+use vek::Clamp;
+
+use crate::{
+    cvars::Cvars,
+    map::{self, Kind, Map, Tile, Vec2f, Vec2u, TILE_SIZE},
+};
+
+pub const EDITOR_HELP: &str = "F2 test-play, mouse paint/pick, Q/E/wheel surface, R rotate, \
+                               1 spawn, 2 base, 3 wall, 4 ground, V validate, F6 save";
+
+#[derive(Debug)]
+pub struct Editor {
+    pub map: Map,
+    /// Where F6 saves the map
+    pub map_path: String,
+    /// Index into the texture list
+    pub surface_index: usize,
+    /// Number of turns counterclockwise like in the map format
+    pub rotation: usize,
+    /// Top left corner of the view in world coords
+    pub camera_top_left: Vec2f,
+    /// Tile under the mouse cursor
+    pub cursor: Option<Vec2u>,
+    /// Result of the last validation or save
+    pub messages: Vec<String>,
+}
+
+impl Editor {
+    pub fn new(map: Map, map_path: String) -> Self {
+        Self {
+            map,
+            map_path,
+            surface_index: 0,
+            rotation: 0,
+            camera_top_left: Vec2f::zero(),
+            cursor: None,
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn process_input(&mut self, cvars: &Cvars) {
+        // Camera
+        let mut dir = Vec2f::zero();
+        if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+            dir.x -= 1.0;
+        }
+        if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+            dir.x += 1.0;
+        }
+        if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+            dir.y -= 1.0;
+        }
+        if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+            dir.y += 1.0;
+        }
+        self.camera_top_left += dir * cvars.cl_editor_scroll_speed * get_frame_time() as f64;
+        // Maps smaller than the screen stay in the top left corner.
+        let screen_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
+        let camera_max = (self.map.maxs() - screen_size).map(|v| v.max(0.0));
+        self.camera_top_left = self.camera_top_left.clamped(Vec2f::zero(), camera_max);
+
+        // Cursor
+        let (mouse_x, mouse_y) = mouse_position();
+        let cursor_pos = Vec2f::new(mouse_x as f64, mouse_y as f64) + self.camera_top_left;
+        let maxs = self.map.maxs();
+        self.cursor = if cursor_pos.x < maxs.x && cursor_pos.y < maxs.y {
+            Some((cursor_pos / TILE_SIZE).as_())
+        } else {
+            None
+        };
+
+        // Selection
+        let surfaces_count = self.map.surfaces().len();
+        let wheel = mouse_wheel().1;
+        if is_key_pressed(KeyCode::Q) || wheel > 0.0 {
+            self.surface_index = (self.surface_index + surfaces_count - 1) % surfaces_count;
+        }
+        if is_key_pressed(KeyCode::E) || wheel < 0.0 {
+            self.surface_index = (self.surface_index + 1) % surfaces_count;
+        }
+        if is_key_pressed(KeyCode::R) {
+            self.rotation = (self.rotation + 1) % 4;
+        }
+        let kind_keys = [
+            (KeyCode::Key1, Kind::Spawn),
+            (KeyCode::Key2, Kind::Base),
+            (KeyCode::Key3, Kind::Wall),
+            (KeyCode::Key4, Kind::Normal),
+        ];
+        for (key_code, kind) in kind_keys {
+            if is_key_pressed(key_code) {
+                self.select_kind(kind);
+            }
+        }
+
+        // Painting
+        if let Some(index) = self.cursor {
+            if is_mouse_button_down(MouseButton::Left) {
+                let old = self.map[index];
+                if old.surface_index != self.surface_index || old.rotation() != self.rotation {
+                    self.map
+                        .set_tile(index, Tile::new(self.surface_index, self.rotation));
+                }
+            }
+            if is_mouse_button_pressed(MouseButton::Right) {
+                let tile = self.map[index];
+                self.surface_index = tile.surface_index;
+                self.rotation = tile.rotation();
+            }
+        }
+
+        if is_key_pressed(KeyCode::V) {
+            self.validate();
+        }
+        if is_key_pressed(KeyCode::F6) {
+            self.save();
+        }
+    }
+
+    /// Select the first surface of the given kind, if any.
+    fn select_kind(&mut self, kind: Kind) {
+        let surfaces = self.map.surfaces();
+        if let Some(index) = surfaces.iter().position(|surface| surface.kind == kind) {
+            self.surface_index = index;
+        }
+    }
+
+    fn validate(&mut self) {
+        self.messages = self
+            .map
+            .validate()
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        if self.messages.is_empty() {
+            self.messages.push("Map OK".to_owned());
+        }
+    }
+
+    fn save(&mut self) {
+        // LATER This doesn't work on the web - offer the map as a download instead.
+        let text = map::save_map(&self.map);
+        let message = match fs::write(&self.map_path, text) {
+            Ok(()) => format!("Saved to {}", self.map_path),
+            Err(err) => format!("Failed to save to {}: {}", self.map_path, err),
+        };
+        dbg_logf!("{}", message);
+        self.messages = vec![message];
+    }
+}
@@ -8,10 +8,12 @@
 //! This is not a violation of the ECS pattern,
 //! because they don't modify game state - they're not behavior.
 
+use std::collections::VecDeque;
+
 use enumn::N;
 use thunderdome::Index;
 
-use crate::{cvars::Cvars, game_state::Input, map::Vec2f};
+use crate::{cvars::Cvars, game_state::Input, map::Vec2f, sys_ai::BotBrain};
 
 #[derive(Debug, Clone)]
 pub struct Player {
@@ -23,6 +25,9 @@ pub struct Player {
     /// - missile input is not reset after death / launching another (results in flying in circles)
     /// - missile stops after player dies / launches another
     pub input: Input,
+    /// Input which hasn't been applied yet with the game time when it became current,
+    /// see `Server::input`.
+    pub input_events: VecDeque<(f64, Input)>,
     pub respawn: Respawn,
     pub death_time: f64,
     pub vehicle: Option<Index>,
@@ -36,6 +41,7 @@ impl Player {
         Self {
             name,
             input: Input::new(),
+            input_events: VecDeque::new(),
             respawn: Respawn::No,
             death_time: 0.0,
             vehicle: None,
@@ -44,6 +50,29 @@ impl Player {
             score: Score::default(),
         }
     }
+
+    /// Apply input events up to `game_time`.
+    ///
+    /// If more than one happened since the last tick, they're merged so quick taps are not lost
+    /// and the latest is applied again next tick so keys don't stay pressed.
+    pub fn apply_input_events(&mut self, game_time: f64) {
+        let mut merged = None;
+        let mut latest = None;
+        while let Some(&(time, input)) = self.input_events.front() {
+            if time > game_time {
+                break;
+            }
+            self.input_events.pop_front();
+            merged = Some(merged.map_or(input, |merged: Input| merged.merged(input)));
+            latest = Some(input);
+        }
+        if let (Some(merged), Some(latest)) = (merged, latest) {
+            self.input = merged;
+            if merged != latest {
+                self.input_events.push_front((game_time, latest));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -69,19 +98,12 @@ impl Score {
 #[derive(Debug, Clone)]
 pub struct Ai {
     pub player: Index,
-    pub movement: i32,
-    pub turning: i32,
-    pub firing: bool,
+    pub brain: Box<dyn BotBrain>,
 }
 
 impl Ai {
-    pub fn new(player: Index) -> Self {
-        Self {
-            player,
-            movement: 0,
-            turning: 0,
-            firing: false,
-        }
+    pub fn new(player: Index, brain: Box<dyn BotBrain>) -> Self {
+        Self { player, brain }
     }
 }
 
@@ -115,16 +137,12 @@ impl Vehicle {
         spawn_time: f64,
         owner: Index,
     ) -> Vehicle {
-        let hitbox = cvars.g_vehicle_hitbox(veh_type);
-        let ammos = vec![
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Mg)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Rail)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Cb)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Rockets)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Hm)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Gm)),
-            Ammo::Loaded(0.0, cvars.g_weapon_reload_ammo(Weapon::Bfg)),
-        ];
+        let hitbox = cvars.g_vehicle(veh_type).hitbox();
+        let ammos = cvars
+            .g_weapons
+            .iter()
+            .map(|stats| Ammo::Loaded(0.0, stats.reload_ammo))
+            .collect();
 
         Vehicle {
             pos,
@@ -147,6 +165,8 @@ impl Vehicle {
     }
 }
 
+pub const VEHICLES_CNT: usize = 3;
+
 #[derive(Debug, Clone, Copy, N)]
 pub enum VehicleType {
     Tank,
@@ -137,7 +137,7 @@ impl InputsPrev {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq)]
 pub struct Input {
     pub left: bool,
     pub right: bool,
@@ -153,6 +153,10 @@ pub struct Input {
     pub horn: bool,
     pub chat: bool,
     pub pause: bool,
+    /// Absolute direction (in world space) the turret should point, e.g. at the mouse cursor.
+    ///
+    /// Overrides the turret step buttons, the turret still turns at `g_turret_turn_speed_deg`.
+    pub turret_angle: Option<f64>,
     // ^ when adding fields, also add them to Debug
 }
 
@@ -184,6 +188,8 @@ impl Input {
             horn: self.horn | other.horn,
             chat: self.chat | other.chat,
             pause: self.pause | other.pause,
+            // The latest wins, an angle can't be merged.
+            turret_angle: other.turret_angle.or(self.turret_angle),
         }
     }
 
@@ -266,6 +272,9 @@ impl Debug for Input {
         if self.pause {
             write!(f, "pause ")?;
         }
+        if let Some(angle) = self.turret_angle {
+            write!(f, "turret_angle {:.0} ", angle.to_degrees())?;
+        }
         write!(f, "}}")?;
         Ok(())
     }
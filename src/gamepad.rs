@@ -0,0 +1,248 @@
+//! Gamepad input - maps sticks, triggers and buttons to `Input`.
+//!
+//! Macroquad doesn't support gamepads yet so on Linux they're read directly
+//! from the joystick devices (`/dev/input/js*`), other platforms currently have none.
+//! The mapping assumes an Xbox-like layout which is what most gamepads report on Linux.
+//!
+//! - left stick or d-pad - movement
+//! - right stick or bumpers - turret
+//! - right trigger - fire, left trigger - mine
+//! - X / Y - previous / next weapon
+//! - B - horn, Back - self destruct, Start - pause
+
+use std::sync::mpsc::{self, Receiver};
+
+use crate::{cvars::Cvars, game_state::Input};
+
+// Axes in the order the linux joystick API reports them.
+const LEFT_X: usize = 0;
+const LEFT_Y: usize = 1;
+const LEFT_TRIGGER: usize = 2;
+const RIGHT_X: usize = 3;
+const RIGHT_Y: usize = 4;
+const RIGHT_TRIGGER: usize = 5;
+const DPAD_X: usize = 6;
+const DPAD_Y: usize = 7;
+const AXES: usize = 8;
+
+// Buttons in the order the linux joystick API reports them.
+const B: usize = 1;
+const X: usize = 2;
+const Y: usize = 3;
+const LEFT_BUMPER: usize = 4;
+const RIGHT_BUMPER: usize = 5;
+const BACK: usize = 6;
+const START: usize = 7;
+const BUTTONS: usize = 11;
+
+/// Size of `struct js_event` - u32 time, i16 value, u8 type, u8 number.
+const EVENT_SIZE: usize = 8;
+const EVENT_BUTTON: u8 = 0x01;
+const EVENT_AXIS: u8 = 0x02;
+/// Set on the synthetic events describing the initial state after opening the device.
+const EVENT_INIT: u8 = 0x80;
+
+/// Current position of all axes (normalized to -1..=1) and buttons of one gamepad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadState {
+    pub axes: [f64; AXES],
+    pub buttons: [bool; BUTTONS],
+}
+
+impl GamepadState {
+    pub fn new() -> Self {
+        let mut axes = [0.0; AXES];
+        // Released triggers are at the negative end.
+        axes[LEFT_TRIGGER] = -1.0;
+        axes[RIGHT_TRIGGER] = -1.0;
+        Self {
+            axes,
+            buttons: [false; BUTTONS],
+        }
+    }
+
+    /// Update the state from a raw `js_event`. Unknown axes and buttons are ignored.
+    pub fn apply_event(&mut self, event: [u8; EVENT_SIZE]) {
+        let value = i16::from_le_bytes([event[4], event[5]]);
+        let kind = event[6] & !EVENT_INIT;
+        let number = event[7] as usize;
+        match kind {
+            EVENT_BUTTON if number < BUTTONS => self.buttons[number] = value != 0,
+            EVENT_AXIS if number < AXES => {
+                // i16::MIN would be slightly below -1.
+                self.axes[number] = (value as f64 / i16::MAX as f64).max(-1.0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Digital input like from a keyboard - the sticks act as 8-way d-pads.
+    pub fn input(&self, cvars: &Cvars) -> Input {
+        let deadzone = cvars.cl_gamepad_deadzone;
+        let mut input = Input::new();
+
+        let (x, y) = stick(self.axes[LEFT_X], self.axes[LEFT_Y], deadzone);
+        input.left = x < 0.0 || self.axes[DPAD_X] < 0.0;
+        input.right = x > 0.0 || self.axes[DPAD_X] > 0.0;
+        // Y points down.
+        input.up = y < 0.0 || self.axes[DPAD_Y] < 0.0;
+        input.down = y > 0.0 || self.axes[DPAD_Y] > 0.0;
+
+        let (x, _) = stick(self.axes[RIGHT_X], self.axes[RIGHT_Y], deadzone);
+        input.turret_left = x < 0.0 || self.buttons[LEFT_BUMPER];
+        input.turret_right = x > 0.0 || self.buttons[RIGHT_BUMPER];
+
+        input.fire = trigger(self.axes[RIGHT_TRIGGER]) > cvars.cl_gamepad_trigger_threshold;
+        input.mine = trigger(self.axes[LEFT_TRIGGER]) > cvars.cl_gamepad_trigger_threshold;
+        input.prev_weapon = self.buttons[X];
+        input.next_weapon = self.buttons[Y];
+        input.horn = self.buttons[B];
+        input.self_destruct = self.buttons[BACK];
+        input.pause = self.buttons[START];
+
+        input
+    }
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply a radial deadzone, then snap to 8 directions.
+///
+/// Each returned component is -1, 0 or 1.
+fn stick(x: f64, y: f64, deadzone: f64) -> (f64, f64) {
+    let len = x.hypot(y);
+    if len <= deadzone {
+        return (0.0, 0.0);
+    }
+    // Within 22.5 degrees of an axis counts as only that direction.
+    let threshold = len * 22.5_f64.to_radians().sin();
+    let snap = |v: f64| if v.abs() > threshold { v.signum() } else { 0.0 };
+    (snap(x), snap(y))
+}
+
+/// Triggers go from -1 (released) to 1, return 0..=1.
+fn trigger(axis: f64) -> f64 {
+    (axis + 1.0) / 2.0
+}
+
+/// All gamepads found at startup, updated by reading their devices in the background.
+///
+/// LATER Hotplugging
+#[derive(Debug)]
+pub struct Gamepads {
+    states: Vec<GamepadState>,
+    events: Receiver<(usize, [u8; EVENT_SIZE])>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let (sender, events) = mpsc::channel();
+        #[allow(unused_mut)] // Only used on linux
+        let mut states = Vec::new();
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::{fs::File, io::Read, thread};
+
+            for i in 0..8 {
+                let path = format!("/dev/input/js{i}");
+                let mut file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(_) => continue,
+                };
+                dbg_logf!("Found gamepad {}: {}", states.len() + 1, path);
+                let index = states.len();
+                states.push(GamepadState::new());
+                let sender = sender.clone();
+                // Reads block so each device gets its own thread.
+                thread::spawn(move || {
+                    let mut event = [0; EVENT_SIZE];
+                    while file.read_exact(&mut event).is_ok() {
+                        if sender.send((index, event)).is_err() {
+                            break;
+                        }
+                    }
+                    dbg_logf!("Gamepad {} disconnected", index + 1);
+                });
+            }
+        }
+        drop(sender);
+
+        Self { states, events }
+    }
+
+    /// Apply all events received since the last call.
+    pub fn poll(&mut self) {
+        while let Ok((index, event)) = self.events.try_recv() {
+            self.states[index].apply_event(event);
+        }
+    }
+
+    /// Input from the gamepad at `index` (0-based), empty if there's no such gamepad.
+    pub fn input(&self, cvars: &Cvars, index: Option<usize>) -> Input {
+        match index.and_then(|index| self.states.get(index)) {
+            Some(state) if cvars.cl_gamepads => state.input(cvars),
+            _ => Input::new(),
+        }
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: u8, number: u8, value: i16) -> [u8; EVENT_SIZE] {
+        let [lo, hi] = value.to_le_bytes();
+        [0, 0, 0, 0, lo, hi, kind, number]
+    }
+
+    #[test]
+    fn test_gamepad_input() {
+        let cvars = Cvars::default();
+        let mut state = GamepadState::new();
+        assert_eq!(state.input(&cvars), Input::new());
+
+        // Inside the deadzone
+        state.apply_event(event(EVENT_AXIS | EVENT_INIT, LEFT_X as u8, -3000));
+        state.apply_event(event(EVENT_AXIS, LEFT_Y as u8, 3000));
+        assert_eq!(state.input(&cvars), Input::new());
+
+        // Diagonal
+        state.apply_event(event(EVENT_AXIS, LEFT_X as u8, -20000));
+        state.apply_event(event(EVENT_AXIS, LEFT_Y as u8, -20000));
+        let input = state.input(&cvars);
+        assert!(input.left && input.up);
+        assert!(!input.right && !input.down);
+
+        // Mostly up
+        state.apply_event(event(EVENT_AXIS, LEFT_X as u8, -3000));
+        let input = state.input(&cvars);
+        assert!(!input.left && input.up);
+
+        state.apply_event(event(EVENT_AXIS, RIGHT_X as u8, i16::MIN));
+        state.apply_event(event(EVENT_BUTTON, RIGHT_BUMPER as u8, 1));
+        state.apply_event(event(EVENT_AXIS, RIGHT_TRIGGER as u8, 10000));
+        state.apply_event(event(EVENT_AXIS, LEFT_TRIGGER as u8, -10000));
+        state.apply_event(event(EVENT_BUTTON, Y as u8, 1));
+        state.apply_event(event(EVENT_BUTTON, 200, 1));
+        state.apply_event(event(EVENT_AXIS, 200, 1));
+        let input = state.input(&cvars);
+        assert!(input.turret_left && input.turret_right);
+        assert!(input.fire && !input.mine);
+        assert!(input.next_weapon && !input.prev_weapon);
+        assert_eq!(state.axes[RIGHT_X], -1.0);
+
+        state.apply_event(event(EVENT_BUTTON, Y as u8, 0));
+        assert!(!state.input(&cvars).next_weapon);
+    }
+}
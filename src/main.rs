@@ -11,23 +11,43 @@
 #[macro_use]
 pub mod debugging; // keep first so the macros are available everywhere
 
+pub mod binds;
+pub mod config;
 pub mod cvars;
+pub mod editor;
 pub mod entities;
 pub mod game_state;
+pub mod gamepad;
 pub mod map;
+pub mod map_gen;
+pub mod map_rotation;
 pub mod mq;
 pub mod rendering;
 pub mod server;
 pub mod sys_ai;
 pub mod systems;
+pub mod thumbnail;
 pub mod timing;
 
-use std::str;
+use std::{cmp::Reverse, fs, path::Path, str};
 
+use ::cvars::SetGet;
 use clap::Parser;
 use macroquad::prelude::*;
 
-use crate::{cvars::Cvars, mq::MacroquadClient, server::Server};
+use thunderdome::Index;
+
+use crate::{
+    binds::MAX_LOCAL_PLAYERS,
+    config::Commands,
+    cvars::{Balance, Cvars, ZOOM_MAX, ZOOM_MIN},
+    editor::Editor,
+    map::{Map, MapMeta, Surface},
+    map_gen::GenParams,
+    map_rotation::MapRotation,
+    mq::MacroquadClient,
+    server::{MatchState, Server},
+};
 
 #[derive(Debug, Parser)]
 struct Opts {
@@ -35,10 +55,60 @@ struct Opts {
     #[arg(long)]
     splitscreen: bool,
 
+    /// Number of local players in splitscreen (overrides --splitscreen).
+    /// There are default keyboard binds for 2, the rest need gamepads or custom binds.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=MAX_LOCAL_PLAYERS as i64))]
+    players: Option<u8>,
+
     /// Set the map to play on (instead of random)
     #[arg(long)]
     map: Option<String>,
 
+    /// Start in the map editor (F2 switches between editing and test-playing)
+    #[arg(long)]
+    edit: bool,
+
+    /// Play on a procedurally generated map instead (0 means random seed).
+    /// Configure it using the sv_mapgen_* cvars.
+    #[arg(long, value_name = "SEED")]
+    generate: Option<u64>,
+
+    /// Check the map given by --map or --generate (or all maps) for problems and exit
+    #[arg(long)]
+    validate: bool,
+
+    /// Save an overview image of the map given by --map or --generate (e.g. `map.png`) and exit
+    #[arg(long, value_name = "PATH")]
+    thumbnail: Option<String>,
+
+    /// Pixels per tile in the --thumbnail image
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    thumbnail_scale: u32,
+
+    /// Gameplay preset: rec_wars (default) or rec_war (closer to the original game).
+    /// Cvars from config files and the command line are applied on top.
+    #[arg(long)]
+    balance: Option<Balance>,
+
+    /// Print all cvars with their values and flags (after applying config files and arguments) and exit
+    #[arg(long)]
+    list_cvars: bool,
+
+    /// Comma separated list of bot brains, bots cycle through them.
+    /// Available: random, idle
+    #[arg(long, value_delimiter = ',', default_value = "random")]
+    brains: Vec<String>,
+
+    /// Play a match between bots for the given number of game seconds
+    /// (or until a match limit is reached) without a window, then print the scoreboard
+    #[arg(long, value_name = "SECONDS")]
+    headless: Option<f64>,
+
     /// Set cvar values - use key value pairs (separated by space).
     /// Example: g_armor 150 hud_names false
     cvars: Vec<String>,
@@ -58,6 +128,111 @@ fn get_opts() -> Opts {
     opts
 }
 
+impl Opts {
+    fn local_players(&self) -> usize {
+        match self.players {
+            Some(players) => players as usize,
+            None if self.splitscreen => 2,
+            None => 1,
+        }
+    }
+}
+
+/// RecWar's surfaces, used unless the map's metadata names a custom tileset.
+const TEX_LIST_PATH: &str = "assets/texture_list.txt";
+
+/// The default map rotation - a subset of maps that are not blatantly broken with the current bots.
+const MAPS: &[&str] = &[
+    //"Arena",
+    //"A simple plan (2)",
+    "Atrium",
+    "Bunkers (2)",
+    "Castle Islands (2)",
+    "Castle Islands (4)",
+    //"Corners (4)",
+    "Delta",
+    "Desert Eagle",
+    //"Joust (2)", // Small map (narrow)
+    //"Large front (2)",
+    //"Oases (4)",
+    "Park",
+    "Roads",
+    "Snow",
+    "Spots (8)",
+    //"Vast Arena",
+    //"extra/6 terrains (2)",
+    //"extra/A Cow Too Far",
+    //"extra/All Water",
+    //"extra/Battlegrounds (2)",
+    "extra/Crossing",           // No spawns, players spawn at random non-wall tiles
+    "extra/Damned Rockets (2)", // Asymmetric CTF, left half like Castly Islands (2), right half has 2 bases
+    //"extra/doom",
+    //"extra/elements",
+    //"extra/Exile (4)", // Tiny, many spawns
+    //"extra/football",
+    "extra/Ice ring",
+    //"extra/ice skating ring (2)",
+    "extra/IceWorld",
+    "extra/I see you (2)", // Like Large Front (2) but without any cover
+    //"extra/Knifflig (2)",
+    //"extra/Large",
+    //"extra/Neutral",
+    "extra/Nile",
+    //"extra/OK Corral (2)", // Small map, not symmetric (upper spawn is closer)
+    //"extra/Peninsulae (3)",
+    //"extra/River Crossings",
+    //"extra/Road To Hell (2)", // Only 4 spawns in a tiny area
+    //"extra/THE Crossing",
+    //"extra/Thomap1 (4)",
+    //"extra/Town on Fire",
+    "extra/twisted (2)",
+    //"extra/winterhardcore",
+    "extra/Yellow and Green",
+    "extra2/Mini Islands (4)",
+    //"extra2/Symmetric",
+    //"extra2/Training room",
+    //"extra2/Winter (4)",
+    //"extra2/World War (2)",
+];
+
+/// Normalize the given map name to a path or pick a random map if none was given.
+fn map_path(map: Option<String>, time_seed: f64) -> String {
+    let mut map_path = map.unwrap_or_else(|| {
+        // Intentionally not using cvars.d_seed here
+        // so that setting the seed doesn't force a specific map.
+        let index = time_seed as usize % MAPS.len();
+        MAPS[index].to_owned()
+    });
+    if !map_path.ends_with(".map") {
+        map_path.push_str(".map");
+    }
+    if !map_path.starts_with("maps/") {
+        map_path.insert_str(0, "maps/");
+    }
+    map_path
+}
+
+/// Paths of the maps to rotate through - `sv_map_rotation` or the default list.
+fn rotation_maps(cvars: &Cvars) -> Vec<String> {
+    let mut names = cvars.sv_map_rotation.names();
+    if names.is_empty() {
+        names = MAPS.iter().map(|&name| name.to_owned()).collect();
+    }
+    names
+        .into_iter()
+        .map(|name| map_path(Some(name), 0.0))
+        .collect()
+}
+
+/// End the match and let players vote between maps from the current `sv_map_rotation`.
+///
+/// The rotation is rebuilt from the cvar each time so changes made during the match apply.
+fn start_vote(server: &mut Server, cvars: &Cvars, rotation: &mut MapRotation, current_map: &str) {
+    rotation.set_maps(rotation_maps(cvars), current_map);
+    let options = rotation.vote_options(cvars.sv_map_vote_options);
+    server.start_vote(cvars, options);
+}
+
 fn window_conf() -> Conf {
     Conf {
         window_title: "RecWars".to_owned(),
@@ -66,141 +241,420 @@ fn window_conf() -> Conf {
         // the window so this is an ugly compromise that should be good enough for most people.
         window_width: 1280,
         window_height: 1024,
-        // LATER Allow resizing - AFAICT there was an issue with infinite memory growth when resizing the render target.
-        // Can't use `fullscreen: true` because of https://github.com/not-fl3/macroquad/issues/237.
-        window_resizable: false,
+        // Splitscreen render targets are recreated (and the old ones deleted) on resize,
+        // see `MacroquadClient::update_window`.
+        // Can't use `fullscreen: true` because of https://github.com/not-fl3/macroquad/issues/237,
+        // use `r_fullscreen` (F11) instead.
+        window_resizable: true,
         ..Default::default()
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn main() {
     let opts = get_opts();
+    if opts.list_cvars {
+        let cvars = init_cvars(&opts, 0.0);
+        for line in config::cvar_list(&cvars) {
+            println!("{line}");
+        }
+    } else if opts.validate {
+        validate_maps(opts);
+    } else if let Some(path) = &opts.thumbnail {
+        export_thumbnail(&opts, path);
+    } else if let Some(duration) = opts.headless {
+        run_headless(opts, duration);
+    } else {
+        macroquad::Window::from_config(window_conf(), client_main(opts));
+    }
+}
 
-    // This is a hack.
-    // It seems that in the browser, MQ redraws the screen several times between here and the main loop
-    // (even though there are no next_frame().await calls) so this doesn't stay up for long.
-    // Let's just redraw it a few times during the loading process so the player sees something is happening.
-    draw_text("Loading...", 400.0, 400.0, 32.0, RED);
-
-    show_mouse(false);
-
+fn init_cvars(opts: &Opts, time_seed: f64) -> Cvars {
     let mut cvars = Cvars::new_rec_wars();
+    if let Some(balance) = opts.balance {
+        // Nothing to keep yet so this also applies the preset's client cvars like the HUD.
+        cvars = Cvars::new_balance(balance);
+        let changes = config::changed_cvars(&Cvars::new_rec_wars(), &cvars);
+        dbg_logf!("Balance {}, changed {} cvars:", balance, changes.len());
+        for change in changes {
+            dbg_logf!("    {}", change);
+        }
+    }
+    // LATER Config files on the web
+    if Path::new(config::AUTOEXEC_PATH).exists() {
+        dbg_logf!("exec {}", config::AUTOEXEC_PATH);
+        if let Err(err) = config::exec(&mut cvars, config::AUTOEXEC_PATH) {
+            dbg_logf!("{}", err);
+        }
+    }
+
     let mut cvars_iter = opts.cvars.iter();
     while let Some(cvar_name) = cvars_iter.next() {
         let str_value = cvars_iter.next().unwrap();
-        cvars.set_str(cvar_name, str_value).unwrap();
-        dbg_logf!("{} = {}", cvar_name, cvars.get_string(cvar_name).unwrap());
+        Commands(&mut cvars).set_str(cvar_name, str_value).unwrap();
+        if let Ok(value) = cvars.get_value(cvar_name) {
+            dbg_logf!("{} = {}", cvar_name, value);
+        }
     }
 
-    let time_seed = macroquad::miniquad::date::now();
     if cvars.d_seed == 0 {
         cvars.d_seed = time_seed.to_bits();
     }
     dbg_logf!("Seed: {}", cvars.d_seed);
 
-    // LATER Load texture list and map in parallel with other assets
-    let tex_list_bytes = load_file("assets/texture_list.txt").await.unwrap();
-    draw_text("Loading...", 400.0, 400.0, 32.0, PURPLE);
-    let tex_list_text = str::from_utf8(&tex_list_bytes).unwrap();
-    let surfaces = map::load_tex_list(tex_list_text);
-
-    // This is a subset of maps that are not blatantly broken with the current bots.
-    let maps = [
-        //"Arena",
-        //"A simple plan (2)",
-        "Atrium",
-        "Bunkers (2)",
-        "Castle Islands (2)",
-        "Castle Islands (4)",
-        //"Corners (4)",
-        "Delta",
-        "Desert Eagle",
-        //"Joust (2)", // Small map (narrow)
-        //"Large front (2)",
-        //"Oases (4)",
-        "Park",
-        "Roads",
-        "Snow",
-        "Spots (8)",
-        //"Vast Arena",
-        //"extra/6 terrains (2)",
-        //"extra/A Cow Too Far",
-        //"extra/All Water",
-        //"extra/Battlegrounds (2)",
-        //"extra/Crossing", // No spawns
-        "extra/Damned Rockets (2)", // Asymmetric CTF, left half like Castly Islands (2), right half has 2 bases
-        //"extra/doom",
-        //"extra/elements",
-        //"extra/Exile (4)", // Tiny, many spawns
-        //"extra/football",
-        "extra/Ice ring",
-        //"extra/ice skating ring (2)",
-        "extra/IceWorld",
-        "extra/I see you (2)", // Like Large Front (2) but without any cover
-        //"extra/Knifflig (2)",
-        //"extra/Large",
-        //"extra/Neutral",
-        "extra/Nile",
-        //"extra/OK Corral (2)", // Small map, not symmetric (upper spawn is closer)
-        //"extra/Peninsulae (3)",
-        //"extra/River Crossings",
-        //"extra/Road To Hell (2)", // Only 4 spawns in a tiny area
-        //"extra/THE Crossing",
-        //"extra/Thomap1 (4)",
-        //"extra/Town on Fire",
-        "extra/twisted (2)",
-        //"extra/winterhardcore",
-        "extra/Yellow and Green",
-        "extra2/Mini Islands (4)",
-        //"extra2/Symmetric",
-        //"extra2/Training room",
-        //"extra2/Winter (4)",
-        //"extra2/World War (2)",
-    ];
-    let mut map_path = opts.map.unwrap_or_else(|| {
-        // Intentionally not using cvars.d_seed here
-        // so that setting the seed doesn't force a specific map.
-        let index = time_seed as usize % maps.len();
-        maps[index].to_owned()
-    });
-    if !map_path.ends_with(".map") {
-        map_path.push_str(".map");
+    cvars
+}
+
+/// Print problems found in the given or generated map or all maps in the `maps` directory.
+fn validate_maps(opts: Opts) {
+    if let Some(seed) = opts.generate {
+        let time_seed = macroquad::miniquad::date::now();
+        let cvars = init_cvars(&opts, time_seed);
+        let surfaces = read_tex_list(TEX_LIST_PATH).unwrap_or_else(|err| panic!("{err}"));
+        let (map, path) = generate_map(&cvars, seed, time_seed, surfaces);
+        print_issues(&path, &map);
+        return;
     }
-    if !map_path.starts_with("maps/") {
-        map_path.insert_str(0, "maps/");
+
+    let paths = if opts.map.is_some() {
+        vec![map_path(opts.map, 0.0)]
+    } else {
+        let mut paths = Vec::new();
+        let mut dirs = vec!["maps".to_owned()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path.to_str().unwrap().to_owned());
+                } else if path.extension().map_or(false, |ext| ext == "map") {
+                    paths.push(path.to_str().unwrap().to_owned());
+                }
+            }
+        }
+        paths.sort();
+        paths
+    };
+
+    for path in paths {
+        match read_map(&path) {
+            Ok(map) => print_issues(&path, &map),
+            Err(err) => println!("{path}: failed to load: {err}"),
+        }
     }
-    dbg_logf!("Map: {}", map_path);
+}
 
-    let map_bytes = load_file(&map_path).await.unwrap();
-    draw_text("Loading...", 400.0, 400.0, 32.0, PURPLE);
-    let map_text = str::from_utf8(&map_bytes).unwrap();
-    let map = map::load_map(map_text, surfaces);
+fn print_issues(path: &str, map: &Map) {
+    let issues = map.validate();
+    let missing_images: Vec<_> = map
+        .surfaces()
+        .iter()
+        .filter(|surface| !Path::new(&surface.image).exists())
+        .collect();
+    if issues.is_empty() && missing_images.is_empty() {
+        println!("{path}: OK");
+    }
+    for issue in issues {
+        println!("{path}: {issue}");
+    }
+    for surface in missing_images {
+        println!(
+            "{path}: missing image for surface {}: {}",
+            surface.name, surface.image
+        );
+    }
+}
+
+/// Generate a map using the `sv_mapgen_*` cvars, seed 0 means random.
+///
+/// Returns the map and where the editor should save it.
+fn generate_map(cvars: &Cvars, seed: u64, time_seed: f64, surfaces: Vec<Surface>) -> (Map, String) {
+    let seed = if seed == 0 { time_seed as u64 } else { seed };
+    dbg_logf!("Map generator seed: {}", seed);
+    let params = GenParams::from_cvars(cvars, seed);
+    let map = map_gen::generate(&params, surfaces)
+        .unwrap_or_else(|err| panic!("Failed to generate map: {err}"));
+    (map, format!("maps/Generated {}.map", seed))
+}
+
+/// Create a server with bots and connect local players to it.
+fn new_server(
+    cvars: &Cvars,
+    map: Map,
+    brains: &[String],
+    local_players: usize,
+) -> (Server, Vec<Index>) {
+    let mut server = Server::new(cvars, map);
+    server.set_brains(brains).unwrap();
+
+    let player_handles = (1..=local_players)
+        .map(|i| server.connect(cvars, &format!("Player {i}")))
+        .collect();
+    (server, player_handles)
+}
+
+/// Replace the server with a new one on `map` and connect the local players again.
+fn restart_server(
+    server: &mut Server,
+    client: &mut MacroquadClient,
+    cvars: &Cvars,
+    map: Map,
+    opts: &Opts,
+    real_time: f64,
+) {
+    let (new_server, player_handles) = new_server(cvars, map, &opts.brains, opts.local_players());
+    *server = new_server;
+    // Don't simulate the time spent loading or editing.
+    server.real_time = real_time;
+    client.set_player_handles(player_handles);
+}
+
+fn read_tex_list(path: &str) -> Result<Vec<Surface>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Failed to read {path}: {err}"))?;
+    map::load_tex_list(&text).map_err(|err| format!("Failed to load texture list {path}: {err}"))
+}
+
+/// Load a map, its metadata sidecar (if there is one) and its tileset without needing a window.
+fn read_map(map_path: &str) -> Result<Map, String> {
+    let mut meta = MapMeta::from_path(map_path);
+    let meta_path = map::meta_path(map_path);
+    if let Ok(meta_text) = fs::read_to_string(&meta_path) {
+        meta = map::load_meta(&meta_text, meta)
+            .map_err(|err| format!("Failed to load map metadata {meta_path}: {err}"))?;
+    }
+    let surfaces = read_tex_list(meta.texture_list.as_deref().unwrap_or(TEX_LIST_PATH))?;
+
+    let map_text =
+        fs::read_to_string(map_path).map_err(|err| format!("Failed to read {map_path}: {err}"))?;
+    let mut map = map::load_map(&map_text, surfaces)
+        .map_err(|err| format!("Failed to load map {map_path}: {err}"))?;
+    map.set_meta(meta);
+    Ok(map)
+}
+
+/// Load the map given by `--map` (random if none) or generate one if `--generate` is set.
+fn read_or_generate_map(opts: &Opts, cvars: &Cvars, time_seed: f64) -> Map {
+    if let Some(seed) = opts.generate {
+        let surfaces = read_tex_list(TEX_LIST_PATH).unwrap_or_else(|err| panic!("{err}"));
+        generate_map(cvars, seed, time_seed, surfaces).0
+    } else {
+        let map_path = map_path(opts.map.clone(), time_seed);
+        dbg_logf!("Map: {}", map_path);
+        read_map(&map_path).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+/// Save an overview of the map as an image without needing a window.
+fn export_thumbnail(opts: &Opts, path: &str) {
+    let time_seed = macroquad::miniquad::date::now();
+    let cvars = init_cvars(opts, time_seed);
+    let map = read_or_generate_map(opts, &cvars, time_seed);
+    thumbnail::save_thumbnail(&map, opts.thumbnail_scale, path)
+        .unwrap_or_else(|err| panic!("Failed to save thumbnail {path}: {err}"));
+    println!("{path}: {}x{} tiles", map.width(), map.height());
+}
+
+/// Play a match with only bots and no window, then print the scoreboard.
+///
+/// This is meant for comparing bot brains (see `--brains`).
+/// It runs as fast as possible, not in real time.
+fn run_headless(opts: Opts, duration: f64) {
+    let time_seed = macroquad::miniquad::date::now();
+    let cvars = init_cvars(&opts, time_seed);
+    let map = read_or_generate_map(&opts, &cvars, time_seed);
 
     let mut server = Server::new(&cvars, map);
+    server.set_brains(&opts.brains).unwrap();
 
-    let player1_handle = server.connect(&cvars, "Player 1");
-    let player2_handle = if opts.splitscreen {
-        Some(server.connect(&cvars, "Player 2"))
+    // Pretend we're running at 60 FPS.
+    let frame_time = 1.0 / 60.0;
+    let mut real_time = 0.0;
+    while server.gs.game_time < duration && !server.match_should_end(&cvars) {
+        real_time += frame_time;
+        server.update(&cvars, real_time);
+    }
+
+    let mut scores: Vec<_> = server
+        .gs
+        .ais
+        .iter()
+        .map(|(_, ai)| {
+            let player = &server.gs.players[ai.player];
+            (player, ai.brain.name())
+        })
+        .collect();
+    scores.sort_by_key(|(player, _)| Reverse(player.score.points(&cvars)));
+    println!(
+        "{:<20} {:<10} {:>6} {:>6} {:>8} {:>6}",
+        "Name", "Brain", "Kills", "Deaths", "Suicides", "Points"
+    );
+    for (player, brain_name) in scores {
+        println!(
+            "{:<20} {:<10} {:>6} {:>6} {:>8} {:>6}",
+            player.name,
+            brain_name,
+            player.score.kills,
+            player.score.deaths,
+            player.score.suicides,
+            player.score.points(&cvars)
+        );
+    }
+}
+
+async fn load_tex_list_file(path: &str) -> Result<Vec<Surface>, String> {
+    let tex_list_bytes = load_file(path)
+        .await
+        .map_err(|err| format!("Failed to read {path}: {err}"))?;
+    draw_text("Loading...", 400.0, 400.0, 32.0, PURPLE);
+    let tex_list_text = str::from_utf8(&tex_list_bytes)
+        .map_err(|err| format!("Failed to load texture list {path}: {err}"))?;
+    map::load_tex_list(tex_list_text)
+        .map_err(|err| format!("Failed to load texture list {path}: {err}"))
+}
+
+/// Load a map, its metadata sidecar (if there is one) and its tileset
+/// using macroquad so it also works on the web.
+async fn load_map_file(map_path: &str) -> Result<Map, String> {
+    // The sidecar is optional, most maps don't have one.
+    let mut meta = MapMeta::from_path(map_path);
+    let meta_path = map::meta_path(map_path);
+    if let Ok(meta_bytes) = load_file(&meta_path).await {
+        let meta_text = str::from_utf8(&meta_bytes)
+            .map_err(|err| format!("Failed to load map metadata {meta_path}: {err}"))?;
+        meta = map::load_meta(meta_text, meta)
+            .map_err(|err| format!("Failed to load map metadata {meta_path}: {err}"))?;
+    }
+    let surfaces =
+        load_tex_list_file(meta.texture_list.as_deref().unwrap_or(TEX_LIST_PATH)).await?;
+
+    let map_bytes = load_file(map_path)
+        .await
+        .map_err(|err| format!("Failed to read {map_path}: {err}"))?;
+    draw_text("Loading...", 400.0, 400.0, 32.0, PURPLE);
+    let map_text = str::from_utf8(&map_bytes)
+        .map_err(|err| format!("Failed to load map {map_path}: {err}"))?;
+    let mut map = map::load_map(map_text, surfaces)
+        .map_err(|err| format!("Failed to load map {map_path}: {err}"))?;
+    map.set_meta(meta);
+    Ok(map)
+}
+
+/// Load the map chosen for the next match.
+///
+/// Maps which fail to load (e.g. a typo in `sv_map_rotation`) are skipped
+/// in favor of the next one in rotation so they don't end the game.
+/// Returns `None` if no map in rotation can be loaded.
+async fn load_next_map(rotation: &mut MapRotation, mut map_path: String) -> Option<(String, Map)> {
+    for _ in 0..=rotation.map_count() {
+        match load_map_file(&map_path).await {
+            Ok(map) => return Some((map_path, map)),
+            Err(err) => {
+                dbg_logf!("{}, skipping it", err);
+                rotation.set_current(&map_path);
+                map_path = rotation.next()?;
+            }
+        }
+    }
+    None
+}
+
+async fn client_main(opts: Opts) {
+    // This is a hack.
+    // It seems that in the browser, MQ redraws the screen several times between here and the main loop
+    // (even though there are no next_frame().await calls) so this doesn't stay up for long.
+    // Let's just redraw it a few times during the loading process so the player sees something is happening.
+    draw_text("Loading...", 400.0, 400.0, 32.0, RED);
+
+    let time_seed = macroquad::miniquad::date::now();
+    let mut cvars = init_cvars(&opts, time_seed);
+
+    // LATER Load texture list and map in parallel with other assets
+    let (map, map_path) = if let Some(seed) = opts.generate {
+        let surfaces = load_tex_list_file(TEX_LIST_PATH)
+            .await
+            .unwrap_or_else(|err| panic!("{err}"));
+        generate_map(&cvars, seed, time_seed, surfaces)
     } else {
-        None
+        let map_path = map_path(opts.map.clone(), time_seed);
+        dbg_logf!("Map: {}", map_path);
+        let map = load_map_file(&map_path)
+            .await
+            .unwrap_or_else(|err| panic!("{err}"));
+        (map, map_path)
     };
+    dbg_logf!("Map name: {}", map.meta().name);
+    if let Some(author) = &map.meta().author {
+        dbg_logf!("Map author: {}", author);
+    }
+
+    let mut rotation = MapRotation::new(rotation_maps(&cvars), cvars.d_seed);
+    rotation.set_current(&map_path);
+    let mut editor = Editor::new(map.clone(), map_path);
+    let mut editing = opts.edit;
+    show_mouse(editing);
+
+    let surfaces = map.surfaces().to_vec();
+    let (mut server, player_handles) = new_server(&cvars, map, &opts.brains, opts.local_players());
     // LATER It can take some time for assets to load but the game is already running on the server.
     //       Load assets first, then connect.
-    let mut client = MacroquadClient::new(&cvars, player1_handle, player2_handle).await;
+    let mut client = MacroquadClient::new(&cvars, &surfaces, player_handles).await;
     draw_text("Loading...", 400.0, 400.0, 32.0, PURPLE);
 
     loop {
         let real_time = get_time();
 
-        server.snapshot_inputs();
+        if is_key_pressed(KeyCode::F1) && !client.console.is_open() {
+            cvars.hud_binds = !cvars.hud_binds;
+        }
+        if is_key_pressed(KeyCode::F11) && !client.console.is_open() {
+            cvars.r_fullscreen = !cvars.r_fullscreen;
+        }
+        client.update_window(&cvars);
 
-        client.process_input(&mut server);
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 && !editing && !client.console.is_open() {
+            // The amount per step differs between platforms, only the direction matters.
+            let zoom = cvars.cl_zoom * cvars.cl_zoom_step.powf(wheel.signum() as f64);
+            cvars.cl_zoom = zoom.clamp(ZOOM_MIN, ZOOM_MAX);
+        }
 
-        server.update(&cvars, real_time);
+        if is_key_pressed(KeyCode::F2) && !client.console.is_open() {
+            if editing {
+                // Test-play the edited map from scratch.
+                let map = editor.map.clone();
+                restart_server(&mut server, &mut client, &cvars, map, &opts, real_time);
+            }
+            editing = !editing;
+            show_mouse(editing);
+        }
+
+        if editing {
+            if !client.console.is_open() {
+                editor.process_input(&cvars);
+            }
+            rendering::render_editor(&mut client, &editor, &cvars);
+        } else {
+            client.process_input(&mut server, &cvars, real_time);
+
+            server.update(&cvars, real_time);
+
+            if server.match_should_end(&cvars) {
+                start_vote(&mut server, &cvars, &mut rotation, &editor.map_path);
+            }
+            if let MatchState::Finished { next_map } = &server.match_state {
+                let (next_map, map) = match load_next_map(&mut rotation, next_map.clone()).await {
+                    Some(loaded) => loaded,
+                    // Better than crashing - play the same map again.
+                    None => (editor.map_path.clone(), server.map.clone()),
+                };
+                rotation.set_current(&next_map);
+                client.set_tileset(map.surfaces()).await;
+                editor = Editor::new(map.clone(), next_map);
+                let real_time = get_time();
+                restart_server(&mut server, &mut client, &cvars, map, &opts, real_time);
+            }
 
-        rendering::render(&mut client, &server, &cvars);
-        client.console.update(&mut cvars);
+            rendering::render(&mut client, &server, &cvars);
+        }
+        client.console.update(&mut Commands(&mut cvars));
 
         let before = get_time();
         next_frame().await;
@@ -210,3 +664,45 @@ async fn main() {
             .add(cvars.d_timing_samples, after - before);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn test_rotation_changed_during_match() {
+        let tex_list_text = fs::read_to_string(TEX_LIST_PATH).unwrap();
+        let surfaces = map::load_tex_list(&tex_list_text).unwrap();
+        let map_text = fs::read_to_string("maps/Atrium.map").unwrap();
+        let map = map::load_map(&map_text, surfaces).unwrap();
+
+        let mut cvars = Cvars {
+            g_match_time_limit: 1.0,
+            sv_map_vote_options: 5,
+            ..Cvars::default()
+        };
+        cvars.set_value("sv_map_rotation", "Atrium, Delta").unwrap();
+        let mut rotation = MapRotation::new(rotation_maps(&cvars), cvars.d_seed);
+        rotation.set_current("maps/Atrium.map");
+        let mut server = Server::new(&cvars, map);
+
+        let mut real_time = 0.0;
+        while !server.match_should_end(&cvars) {
+            real_time += 1.0 / 60.0;
+            server.update(&cvars, real_time);
+        }
+        cvars.set_value("sv_map_rotation", "Park, Snow").unwrap();
+        start_vote(&mut server, &cvars, &mut rotation, "maps/Atrium.map");
+
+        match &server.match_state {
+            MatchState::Voting(vote) => {
+                let mut options = vote.options.clone();
+                options.sort();
+                assert_eq!(options, vec!["maps/Park.map", "maps/Snow.map"]);
+            }
+            _ => panic!("expected voting"),
+        }
+    }
+}
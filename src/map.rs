@@ -1,10 +1,17 @@
 //! Map data (everything static during a match) and coordinate system
 
-use std::f64::consts::PI;
-use std::ops::Index;
+use std::{
+    error::Error,
+    f64::consts::PI,
+    fmt::{self, Display, Formatter},
+    mem,
+    ops::Index,
+    str::FromStr,
+};
 
 use enumn::N;
 use rand::{prelude::SmallRng, Rng};
+use strum_macros::{Display, EnumString};
 use vek::{approx::AbsDiffEq, Clamp, Mat2, Vec2};
 
 /// Position in world or screen space.
@@ -63,12 +70,16 @@ pub struct Map {
     tiles: Vec<Vec<Tile>>,
     spawns: Vec<Vec2u>,
     bases: Vec<Vec2u>,
+    nonwalls_count: usize,
+    meta: MapMeta,
 }
 
 impl Map {
-    fn new(tiles: Vec<Vec<Tile>>, surfaces: Vec<Surface>) -> Self {
+    /// `tiles` are rows, each tile's `surface_index` must be valid in `surfaces`.
+    pub fn new(tiles: Vec<Vec<Tile>>, surfaces: Vec<Surface>) -> Self {
         let mut spawns = Vec::new();
         let mut bases = Vec::new();
+        let mut nonwalls_count = 0;
         for (r, row) in tiles.iter().enumerate() {
             for (c, tile) in row.iter().enumerate() {
                 let kind = surfaces[tile.surface_index].kind;
@@ -77,6 +88,9 @@ impl Map {
                 } else if kind == Kind::Base {
                     bases.push(Vec2u::new(c, r));
                 }
+                if kind != Kind::Wall {
+                    nonwalls_count += 1;
+                }
             }
         }
         Map {
@@ -84,9 +98,20 @@ impl Map {
             tiles,
             spawns,
             bases,
+            nonwalls_count,
+            meta: MapMeta::default(),
         }
     }
 
+    /// Information from the metadata sidecar, if any.
+    pub fn meta(&self) -> &MapMeta {
+        &self.meta
+    }
+
+    pub fn set_meta(&mut self, meta: MapMeta) {
+        self.meta = meta;
+    }
+
     pub fn height(&self) -> usize {
         self.tiles.len()
     }
@@ -150,6 +175,15 @@ impl Map {
         &self.surfaces[surface_index]
     }
 
+    /// Kind of the tile at `index` or `None` if it's outside the map.
+    fn kind_at_index(&self, index: Vec2u) -> Option<Kind> {
+        if index.x < self.width() && index.y < self.height() {
+            Some(self.surface_at_index(index).kind)
+        } else {
+            None
+        }
+    }
+
     /// Is `pos` outside the map or inside a wall?
     pub fn is_wall(&self, pos: Vec2f) -> bool {
         if pos.x <= 0.0 {
@@ -241,20 +275,47 @@ impl Map {
         }
     }
 
+    pub fn surfaces(&self) -> &[Surface] {
+        &self.surfaces
+    }
+
+    /// Replace the tile at `index` and update spawns, bases, etc.
+    pub fn set_tile(&mut self, index: Vec2u, tile: Tile) {
+        self.tiles[index.y][index.x] = tile;
+        // Simply recalculate everything - it's fast enough for the editor.
+        let tiles = mem::take(&mut self.tiles);
+        let surfaces = mem::take(&mut self.surfaces);
+        let meta = mem::take(&mut self.meta);
+        *self = Map::new(tiles, surfaces);
+        self.meta = meta;
+    }
+
     pub fn spawns(&self) -> &Vec<Vec2u> {
         &self.spawns
     }
 
-    // LATER remove all #[allow(dead_code)] here (or the fns if they turn out useless)
-
-    #[allow(dead_code)]
     pub fn bases(&self) -> &Vec<Vec2u> {
         &self.bases
     }
 
+    /// How many vehicles can spawn without sharing a spawn.
+    ///
+    /// On maps without spawns, vehicles spawn on random non-wall tiles instead.
+    pub fn spawns_count(&self) -> usize {
+        if self.spawns.is_empty() {
+            self.nonwalls_count
+        } else {
+            self.spawns.len()
+        }
+    }
+
     /// Returns (pos, angle).
+    ///
+    /// Falls back to a random non-wall tile on maps without spawns.
     pub fn random_spawn(&self, rng: &mut SmallRng) -> (Vec2f, f64) {
-        // TODO maps with no spawns (or even all walls)
+        if self.spawns.is_empty() {
+            return self.random_nonwall(rng);
+        }
         let i = rng.gen_range(0..self.spawns().len());
         let index = self.spawns()[i];
         let pos = self.tile_center(index);
@@ -263,7 +324,13 @@ impl Map {
     }
 
     /// Returns (pos, angle).
+    ///
+    /// On maps which are all walls, returns the map's center.
+    /// Vehicles will be stuck there but at least the match can start.
     pub fn random_nonwall(&self, rng: &mut SmallRng) -> (Vec2f, f64) {
+        if self.nonwalls_count == 0 {
+            return (self.maxs() / 2.0, 0.0);
+        }
         loop {
             let c = rng.gen_range(0..self.width());
             let r = rng.gen_range(0..self.height());
@@ -275,6 +342,121 @@ impl Map {
             }
         }
     }
+
+    /// Label connected areas of non-wall tiles.
+    ///
+    /// Returns the region index of each tile (`None` for walls) and the number of regions.
+    /// Tiles are connected only horizontally and vertically,
+    /// vehicles can't squeeze between diagonally touching walls.
+    pub fn regions(&self) -> (Vec<Vec<Option<usize>>>, usize) {
+        let mut regions = vec![vec![None; self.width()]; self.height()];
+        let mut regions_count = 0;
+        let mut stack = Vec::new();
+        for r in 0..self.height() {
+            for c in 0..self.width() {
+                let start = Vec2u::new(c, r);
+                if regions[r][c].is_some() || self.surface_at_index(start).kind == Kind::Wall {
+                    continue;
+                }
+
+                // Flood fill
+                regions[r][c] = Some(regions_count);
+                stack.push(start);
+                while let Some(index) = stack.pop() {
+                    let mut neighbors = Vec::with_capacity(4);
+                    if index.x > 0 {
+                        neighbors.push(Vec2u::new(index.x - 1, index.y));
+                    }
+                    if index.x + 1 < self.width() {
+                        neighbors.push(Vec2u::new(index.x + 1, index.y));
+                    }
+                    if index.y > 0 {
+                        neighbors.push(Vec2u::new(index.x, index.y - 1));
+                    }
+                    if index.y + 1 < self.height() {
+                        neighbors.push(Vec2u::new(index.x, index.y + 1));
+                    }
+                    for n in neighbors {
+                        if regions[n.y][n.x].is_none()
+                            && self.surface_at_index(n).kind != Kind::Wall
+                        {
+                            regions[n.y][n.x] = Some(regions_count);
+                            stack.push(n);
+                        }
+                    }
+                }
+                regions_count += 1;
+            }
+        }
+        (regions, regions_count)
+    }
+
+    /// Find problems which make the map unplayable or unfair.
+    ///
+    /// An empty result means the map is OK.
+    pub fn validate(&self) -> Vec<MapIssue> {
+        let mut issues = Vec::new();
+
+        if self.nonwalls_count == 0 {
+            issues.push(MapIssue::AllWalls);
+        }
+
+        if self.spawns.is_empty() {
+            issues.push(MapIssue::NoSpawns);
+        } else {
+            // Spawns in the region with the most spawns are considered reachable,
+            // the rest are stuck somewhere else.
+            let (regions, regions_count) = self.regions();
+            let mut spawns_per_region = vec![0; regions_count];
+            for spawn in &self.spawns {
+                spawns_per_region[regions[spawn.y][spawn.x].unwrap()] += 1;
+            }
+            let main_region = (0..regions_count)
+                .max_by_key(|&region| spawns_per_region[region])
+                .unwrap();
+            let unreachable: Vec<_> = self
+                .spawns
+                .iter()
+                .copied()
+                .filter(|spawn| regions[spawn.y][spawn.x] != Some(main_region))
+                .collect();
+            if !unreachable.is_empty() {
+                issues.push(MapIssue::UnreachableSpawns(unreachable));
+            }
+        }
+
+        // Without team assignments, assume the usual 2 teams.
+        let mut teams: Vec<_> = self.meta.team_spawns.iter().map(|ts| ts.team).collect();
+        teams.sort_unstable();
+        teams.dedup();
+        let teams_count = teams.len().max(2);
+        if self.bases.len() % teams_count != 0 {
+            issues.push(MapIssue::UnbalancedBases(self.bases.len()));
+        }
+
+        for team_spawn in &self.meta.team_spawns {
+            let index = team_spawn.index;
+            if self.kind_at_index(index) != Some(Kind::Spawn) {
+                issues.push(MapIssue::MisplacedEntity("team spawn", index));
+            }
+        }
+        for pickup in &self.meta.pickups {
+            if matches!(self.kind_at_index(pickup.index), None | Some(Kind::Wall)) {
+                issues.push(MapIssue::MisplacedEntity("pickup", pickup.index));
+            }
+        }
+        for &index in &self.meta.cow_spawns {
+            if matches!(self.kind_at_index(index), None | Some(Kind::Wall)) {
+                issues.push(MapIssue::MisplacedEntity("cow spawn", index));
+            }
+        }
+
+        if self.meta.modes.contains(&GameMode::CaptureTheCow) && self.bases.len() < 2 {
+            issues.push(MapIssue::MissingBases(GameMode::CaptureTheCow));
+        }
+
+        issues
+    }
 }
 
 impl Index<Vec2u> for Map {
@@ -300,6 +482,21 @@ pub struct Tile {
     pub angle: f64,
 }
 
+impl Tile {
+    /// `rotation` is the number of turns counterclockwise like in the map format.
+    pub fn new(surface_index: usize, rotation: usize) -> Self {
+        Self {
+            surface_index,
+            angle: (rotation % 4) as f64 * -PI / 2.0,
+        }
+    }
+
+    /// Number of turns counterclockwise (0-3) like in the map format.
+    pub fn rotation(&self) -> usize {
+        (-self.angle / (PI / 2.0)).round().rem_euclid(4.0) as usize
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Surface {
     pub name: String,
@@ -308,15 +505,18 @@ pub struct Surface {
     pub friction: f32,
     /// Maybe a multiplier for speed
     pub speed: f32,
+    /// Path to the tile's texture
+    pub image: String,
 }
 
 impl Surface {
-    fn new(name: String, kind: Kind, friction: f32, speed: f32) -> Self {
+    fn new(name: String, kind: Kind, friction: f32, speed: f32, image: String) -> Self {
         Self {
             name,
             kind,
             friction,
             speed,
+            image,
         }
     }
 }
@@ -341,64 +541,476 @@ pub enum Kind {
     Base = 5,
 }
 
-pub fn load_map(text: &str, surfaces: Vec<Surface>) -> Map {
-    // TODO handle both CRLF and LF properly
+/// A problem found by `Map::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapIssue {
+    /// There are no non-wall tiles, vehicles get stuck in the map's center.
+    AllWalls,
+    /// Vehicles spawn on random non-wall tiles instead.
+    NoSpawns,
+    /// Spawns which are walled off from the area with the most spawns.
+    UnreachableSpawns(Vec<Vec2u>),
+    /// Bases can't be split evenly between teams (2 unless the metadata assigns spawns to more).
+    UnbalancedBases(usize),
+    /// An entity from the metadata is outside the map, in a wall
+    /// or in case of team spawns not on a spawn tile.
+    MisplacedEntity(&'static str, Vec2u),
+    /// The metadata lists a mode which needs at least 2 bases.
+    MissingBases(GameMode),
+}
+
+impl Display for MapIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::AllWalls => write!(f, "all tiles are walls"),
+            MapIssue::NoSpawns => write!(f, "no spawns"),
+            MapIssue::UnreachableSpawns(spawns) => {
+                write!(f, "{} unreachable spawns:", spawns.len())?;
+                for spawn in spawns {
+                    write!(f, " ({}, {})", spawn.x, spawn.y)?;
+                }
+                Ok(())
+            }
+            MapIssue::UnbalancedBases(count) => {
+                write!(f, "bases can't be split evenly between teams: {}", count)
+            }
+            MapIssue::MisplacedEntity(what, index) => {
+                write!(f, "misplaced {} at ({}, {})", what, index.x, index.y)
+            }
+            MapIssue::MissingBases(mode) => write!(f, "mode {} needs at least 2 bases", mode),
+        }
+    }
+}
+
+/// Information about a map which doesn't fit into the original RecWar format.
+///
+/// Stored in an optional sidecar file next to the map (see `meta_path` and `load_meta`).
+/// Indices are (col, row) like everywhere else.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapMeta {
+    pub name: String,
+    pub author: Option<String>,
+    /// Recommended number of human players
+    pub players: Option<usize>,
+    /// Recommended max number of bots
+    pub bots: Option<usize>,
+    /// Modes the map is designed for, empty means unknown
+    pub modes: Vec<GameMode>,
+    /// Spawns reserved for a team in team modes.
+    /// Spawns not listed here are shared.
+    pub team_spawns: Vec<TeamSpawn>,
+    pub pickups: Vec<Pickup>,
+    /// Where the cow can appear in Capture the Cow
+    pub cow_spawns: Vec<Vec2u>,
+    /// Custom tileset to use instead of the default texture list (see `load_tex_list`)
+    pub texture_list: Option<String>,
+}
+
+impl MapMeta {
+    /// Defaults for maps without a sidecar.
+    ///
+    /// RecWar maps encode the player count in the file name, e.g. `Bunkers (2).map`.
+    pub fn from_path(map_path: &str) -> Self {
+        let file_name = map_path.rsplit('/').next().unwrap();
+        let stem = file_name.strip_suffix(".map").unwrap_or(file_name);
+        let mut meta = MapMeta {
+            name: stem.to_owned(),
+            ..MapMeta::default()
+        };
+        if let Some(rest) = stem.strip_suffix(')') {
+            if let Some((name, players)) = rest.rsplit_once(" (") {
+                if let Ok(players) = players.parse() {
+                    meta.name = name.to_owned();
+                    meta.players = Some(players);
+                }
+            }
+        }
+        meta
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum GameMode {
+    /// Free for all
+    Ffa,
+    TeamWar,
+    CaptureTheCow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TeamSpawn {
+    /// 0-based
+    pub team: usize,
+    pub index: Vec2u,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pickup {
+    /// What to spawn there - pickups don't exist yet so it's not checked.
+    pub name: String,
+    pub index: Vec2u,
+}
+
+/// Why a map or texture list failed to load and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, counted in chars
+    pub column: usize,
+    pub reason: ParseErrorReason,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, reason: ParseErrorReason) -> Self {
+        Self {
+            line,
+            column,
+            reason,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.reason
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorReason {
+    /// The text contains no data at all
+    Empty,
+    /// Expected a non-negative integer or a decimal number
+    InvalidNumber(String),
+    /// The row has a different number of tiles than the first row
+    RaggedRow { expected: usize, found: usize },
+    /// The tile refers to a surface which is not in the texture list
+    SurfaceOutOfRange {
+        surface_index: usize,
+        surfaces: usize,
+    },
+    /// The line has the wrong number of whitespace separated fields
+    WrongFieldCount { expected: usize, found: usize },
+    /// The texture list entry has a kind which doesn't match any `Kind`
+    InvalidKind(u8),
+    /// The metadata line starts with a key which is not recognized
+    UnknownKey(String),
+    /// The metadata lists a mode which doesn't match any `GameMode`
+    InvalidMode(String),
+}
+
+impl Display for ParseErrorReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorReason::Empty => write!(f, "no data"),
+            ParseErrorReason::InvalidNumber(s) => write!(f, "invalid number \"{}\"", s),
+            ParseErrorReason::RaggedRow { expected, found } => write!(
+                f,
+                "row has {} tiles but the first row has {}",
+                found, expected
+            ),
+            ParseErrorReason::SurfaceOutOfRange {
+                surface_index,
+                surfaces,
+            } => write!(
+                f,
+                "surface index {} is out of range, the texture list only has {} surfaces",
+                surface_index, surfaces
+            ),
+            ParseErrorReason::WrongFieldCount { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            ParseErrorReason::InvalidKind(kind) => write!(f, "unknown surface kind {}", kind),
+            ParseErrorReason::UnknownKey(key) => write!(f, "unknown key \"{}\"", key),
+            ParseErrorReason::InvalidMode(mode) => write!(f, "unknown mode \"{}\"", mode),
+        }
+    }
+}
+
+/// Split `text` into non-empty lines and each line into whitespace separated words.
+///
+/// Accepts both CRLF and LF, ignores extra whitespace.
+/// Returns the words with their 1-based line and column numbers.
+fn words(text: &str) -> impl Iterator<Item = (usize, Vec<(usize, &str)>)> {
+    text.lines().enumerate().filter_map(|(line_index, line)| {
+        let mut words = Vec::new();
+        let mut start = None;
+        for (column_index, (byte_index, c)) in line.char_indices().enumerate() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some((column_index, byte_index)),
+                (true, Some((column, begin))) => {
+                    words.push((column + 1, &line[begin..byte_index]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some((column, begin)) = start {
+            words.push((column + 1, &line[begin..]));
+        }
+
+        if words.is_empty() {
+            None
+        } else {
+            Some((line_index + 1, words))
+        }
+    })
+}
+
+fn parse_word<T: FromStr>(line: usize, column: usize, word: &str) -> Result<T, ParseError> {
+    word.parse().map_err(|_| {
+        ParseError::new(
+            line,
+            column,
+            ParseErrorReason::InvalidNumber(word.to_owned()),
+        )
+    })
+}
+
+/// Parse the original RecWar map format.
+///
+/// Each line is a row of tiles, each tile is `surface_index * 4 + rotation`
+/// where `surface_index` is an index into `surfaces` (loaded from texture_list.txt).
+pub fn load_map(text: &str, surfaces: Vec<Surface>) -> Result<Map, ParseError> {
     // TODO move to Map::new()?
-    let tiles = text
-        .split_terminator("\r\n")
-        .map(|line| {
-            line.split(' ')
-                .map(|tile| {
-                    let val: usize = tile.parse().unwrap();
-                    // rotation is number of turns counterclockwise
-                    // angle is clockwise (see Vec2f for coord system explanation)
-                    // g_spawn: rotation - angle - meaning
-                    // 0    0           right
-                    // 1    -1/2*PI     up
-                    // 2    -PI         left
-                    // 3    -3/2*PI     down
-                    let rotation = val % 4;
-                    Tile {
-                        surface_index: val / 4,
-                        angle: rotation as f64 * -PI / 2.0,
-                    }
-                })
-                .collect()
+    let mut tiles: Vec<Vec<Tile>> = Vec::new();
+    for (line, words) in words(text) {
+        let mut row = Vec::new();
+        for (column, word) in words {
+            let val: usize = parse_word(line, column, word)?;
+            // rotation is number of turns counterclockwise
+            // angle is clockwise (see Vec2f for coord system explanation)
+            // g_spawn: rotation - angle - meaning
+            // 0    0           right
+            // 1    -1/2*PI     up
+            // 2    -PI         left
+            // 3    -3/2*PI     down
+            let rotation = val % 4;
+            let surface_index = val / 4;
+            if surface_index >= surfaces.len() {
+                let reason = ParseErrorReason::SurfaceOutOfRange {
+                    surface_index,
+                    surfaces: surfaces.len(),
+                };
+                return Err(ParseError::new(line, column, reason));
+            }
+            row.push(Tile::new(surface_index, rotation));
+        }
+
+        if let Some(first) = tiles.first() {
+            if row.len() != first.len() {
+                let reason = ParseErrorReason::RaggedRow {
+                    expected: first.len(),
+                    found: row.len(),
+                };
+                return Err(ParseError::new(line, 1, reason));
+            }
+        }
+        tiles.push(row);
+    }
+
+    if tiles.is_empty() {
+        return Err(ParseError::new(1, 1, ParseErrorReason::Empty));
+    }
+
+    Ok(Map::new(tiles, surfaces))
+}
+
+/// Serialize the map into the original RecWar format (see `load_map`).
+///
+/// Uses CRLF without a trailing newline like the original maps.
+pub fn save_map(map: &Map) -> String {
+    let rows: Vec<_> = map
+        .tiles
+        .iter()
+        .map(|row| {
+            let vals: Vec<_> = row
+                .iter()
+                .map(|tile| (tile.surface_index * 4 + tile.rotation()).to_string())
+                .collect();
+            vals.join(" ")
         })
         .collect();
-    Map::new(tiles, surfaces)
-}
-
-pub fn load_tex_list(text: &str) -> Vec<Surface> {
-    // TODO handle both CRLF and LF properly OR use cvars instead
-    // if using cvars, update load_map docs
-    text.split_terminator("\r\n")
-        .map(|line| {
-            let mut parts = line.split(' ');
-            let name = parts.next().unwrap();
-            let kind_num = parts.next().unwrap().parse().unwrap();
-            let friction = parts.next().unwrap().parse().unwrap();
-            let speed = parts.next().unwrap().parse().unwrap();
-
-            let kind = Kind::n(kind_num).unwrap();
-            Surface::new(name.to_owned(), kind, friction, speed)
-        })
-        .collect()
+    rows.join("\r\n")
+}
+
+/// Where the metadata sidecar of a map is - next to it with a `.meta` extension.
+pub fn meta_path(map_path: &str) -> String {
+    let stem = map_path.strip_suffix(".map").unwrap_or(map_path);
+    format!("{}.meta", stem)
+}
+
+/// Parse a metadata sidecar, starting from `meta` (usually `MapMeta::from_path`).
+///
+/// Each line is a key followed by values, lines starting with `#` are comments:
+/// ```text
+/// name A simple plan
+/// author Someone
+/// players 2
+/// bots 6
+/// modes ffa team_war capture_the_cow
+/// team_spawn <team> <col> <row>
+/// pickup <name> <col> <row>
+/// cow_spawn <col> <row>
+/// texture_list <path>
+/// ```
+pub fn load_meta(text: &str, mut meta: MapMeta) -> Result<MapMeta, ParseError> {
+    for (line, words) in words(text) {
+        let (key_column, key) = words[0];
+        if key.starts_with('#') {
+            continue;
+        }
+
+        let check_count = |expected| {
+            if words.len() == expected {
+                Ok(())
+            } else {
+                let reason = ParseErrorReason::WrongFieldCount {
+                    expected,
+                    found: words.len(),
+                };
+                Err(ParseError::new(line, key_column, reason))
+            }
+        };
+        let parse_index = |i: usize| -> Result<Vec2u, ParseError> {
+            let (c_column, c) = words[i];
+            let (r_column, r) = words[i + 1];
+            Ok(Vec2u::new(
+                parse_word(line, c_column, c)?,
+                parse_word(line, r_column, r)?,
+            ))
+        };
+        let rest = || {
+            let values: Vec<_> = words[1..].iter().map(|&(_, word)| word).collect();
+            values.join(" ")
+        };
+
+        match key {
+            // Names can contain spaces
+            "name" | "author" if words.len() < 2 => check_count(2)?,
+            "name" => meta.name = rest(),
+            "author" => meta.author = Some(rest()),
+            "players" => {
+                check_count(2)?;
+                meta.players = Some(parse_word(line, words[1].0, words[1].1)?);
+            }
+            "bots" => {
+                check_count(2)?;
+                meta.bots = Some(parse_word(line, words[1].0, words[1].1)?);
+            }
+            "modes" => {
+                meta.modes.clear();
+                for &(column, word) in &words[1..] {
+                    let mode = word.parse().map_err(|_| {
+                        let reason = ParseErrorReason::InvalidMode(word.to_owned());
+                        ParseError::new(line, column, reason)
+                    })?;
+                    meta.modes.push(mode);
+                }
+            }
+            "team_spawn" => {
+                check_count(4)?;
+                let team = parse_word(line, words[1].0, words[1].1)?;
+                let index = parse_index(2)?;
+                meta.team_spawns.push(TeamSpawn { team, index });
+            }
+            "pickup" => {
+                check_count(4)?;
+                let name = words[1].1.to_owned();
+                let index = parse_index(2)?;
+                meta.pickups.push(Pickup { name, index });
+            }
+            "cow_spawn" => {
+                check_count(3)?;
+                meta.cow_spawns.push(parse_index(1)?);
+            }
+            "texture_list" => {
+                check_count(2)?;
+                meta.texture_list = Some(words[1].1.to_owned());
+            }
+            _ => {
+                let reason = ParseErrorReason::UnknownKey(key.to_owned());
+                return Err(ParseError::new(line, key_column, reason));
+            }
+        }
+    }
+
+    Ok(meta)
+}
+
+/// Parse a texture list - each line is `name kind friction speed [image]`.
+///
+/// RecWar's texture list doesn't have the image column,
+/// those surfaces use `assets/tiles/<name>.bmp`.
+/// Custom tilesets can use any path relative to the game's root directory.
+pub fn load_tex_list(text: &str) -> Result<Vec<Surface>, ParseError> {
+    let mut surfaces = Vec::new();
+    for (line, words) in words(text) {
+        if words.len() != 4 && words.len() != 5 {
+            let reason = ParseErrorReason::WrongFieldCount {
+                expected: words.len().clamp(4, 5),
+                found: words.len(),
+            };
+            return Err(ParseError::new(line, 1, reason));
+        }
+        let name = words[0].1;
+        let kind_num = parse_word(line, words[1].0, words[1].1)?;
+        let friction = parse_word(line, words[2].0, words[2].1)?;
+        let speed = parse_word(line, words[3].0, words[3].1)?;
+
+        let kind = Kind::n(kind_num).ok_or_else(|| {
+            ParseError::new(line, words[1].0, ParseErrorReason::InvalidKind(kind_num))
+        })?;
+        let image = match words.get(4) {
+            Some((_, image)) => (*image).to_owned(),
+            None => format!("assets/tiles/{}.bmp", name),
+        };
+        surfaces.push(Surface::new(name.to_owned(), kind, friction, speed, image));
+    }
+
+    if surfaces.is_empty() {
+        return Err(ParseError::new(1, 1, ParseErrorReason::Empty));
+    }
+
+    Ok(surfaces)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::fs;
+    use std::{fs, path::Path};
 
+    use rand::SeedableRng;
     use walkdir::WalkDir;
 
     #[test]
     fn test_loading_tex_list() {
         let text = fs::read_to_string("assets/texture_list.txt").unwrap();
-        let surfaces = load_tex_list(&text);
+        let surfaces = load_tex_list(&text).unwrap();
         assert_ne!(surfaces.len(), 0);
+        for surface in &surfaces {
+            assert!(Path::new(&surface.image).exists(), "{}", surface.image);
+        }
+        assert_eq!(surfaces[0].image, "assets/tiles/g1.bmp");
+
+        let surfaces = load_tex_list(
+            "lava 2 0.5 1 maps/tiles/lava.png
+g1 0 1 1",
+        )
+        .unwrap();
+        assert_eq!(surfaces[0].name, "lava");
+        assert_eq!(surfaces[0].kind, Kind::Wall);
+        assert_eq!(surfaces[0].image, "maps/tiles/lava.png");
+        assert_eq!(surfaces[1].image, "assets/tiles/g1.bmp");
     }
 
     #[test]
@@ -406,7 +1018,7 @@ mod tests {
         let mut cnt = 0;
 
         let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
-        let surfaces = load_tex_list(&tex_list_text);
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
         for entry in WalkDir::new("maps") {
             let entry = entry.unwrap();
             let is_map = entry.file_name().to_str().unwrap().ends_with(".map");
@@ -416,7 +1028,7 @@ mod tests {
 
             dbg!(entry.file_name());
             let map_text = fs::read_to_string(entry.path()).unwrap();
-            let map = load_map(&map_text, surfaces.clone());
+            let map = load_map(&map_text, surfaces.clone()).unwrap();
             assert_ne!(map.width(), 0);
             assert_ne!(map.height(), 0);
             cnt += 1;
@@ -424,12 +1036,242 @@ mod tests {
         assert_ne!(cnt, 0);
     }
 
+    #[test]
+    fn test_loading_line_endings_and_whitespace() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text.replace("\r\n", "\n")).unwrap();
+        assert_eq!(surfaces.len(), load_tex_list(&tex_list_text).unwrap().len());
+
+        let crlf = load_map("0 1 2\r\n3 4 5\r\n", surfaces.clone()).unwrap();
+        let lf = load_map("0 1 2\n3 4 5", surfaces.clone()).unwrap();
+        let messy = load_map(" 0  1 2 \n\n3\t4 5\r\n\r\n", surfaces).unwrap();
+        for map in [crlf, lf, messy] {
+            assert_eq!(map.size(), Vec2u::new(3, 2));
+            assert_eq!(map.col_row(2, 1).surface_index, 1);
+        }
+    }
+
+    #[test]
+    fn test_loading_errors() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+
+        let err = load_map("0 1\n0 x1", surfaces.clone()).unwrap_err();
+        let reason = ParseErrorReason::InvalidNumber("x1".to_owned());
+        assert_eq!(err, ParseError::new(2, 3, reason));
+        assert_eq!(err.to_string(), "line 2, column 3: invalid number \"x1\"");
+
+        let err = load_map("0 1\n0 1 2\n", surfaces.clone()).unwrap_err();
+        let reason = ParseErrorReason::RaggedRow {
+            expected: 2,
+            found: 3,
+        };
+        assert_eq!(err, ParseError::new(2, 1, reason));
+
+        let err = load_map("0 1 9999", surfaces.clone()).unwrap_err();
+        let reason = ParseErrorReason::SurfaceOutOfRange {
+            surface_index: 2499,
+            surfaces: surfaces.len(),
+        };
+        assert_eq!(err, ParseError::new(1, 5, reason));
+
+        let err = load_map(" \r\n", surfaces).unwrap_err();
+        assert_eq!(err, ParseError::new(1, 1, ParseErrorReason::Empty));
+
+        let err = load_tex_list("g1 0 1 1\ng2 0 1").unwrap_err();
+        let reason = ParseErrorReason::WrongFieldCount {
+            expected: 4,
+            found: 3,
+        };
+        assert_eq!(err, ParseError::new(2, 1, reason));
+
+        let err = load_tex_list("g1 0 1 1 g1.bmp extra").unwrap_err();
+        let reason = ParseErrorReason::WrongFieldCount {
+            expected: 5,
+            found: 6,
+        };
+        assert_eq!(err, ParseError::new(1, 1, reason));
+
+        let err = load_tex_list("g1 9 1 1").unwrap_err();
+        assert_eq!(err, ParseError::new(1, 4, ParseErrorReason::InvalidKind(9)));
+
+        let err = load_tex_list("g1 0 1 fast").unwrap_err();
+        let reason = ParseErrorReason::InvalidNumber("fast".to_owned());
+        assert_eq!(err, ParseError::new(1, 8, reason));
+    }
+
+    #[test]
+    fn test_validation() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+        // 0 is grass, 16 is wall, 36 is spawn, 60 is base
+        let load = |text: &str| load_map(text, surfaces.clone()).unwrap();
+
+        let ok = load("36 0 60\n0 16 0\n60 0 36");
+        assert_eq!(ok.validate(), Vec::new());
+
+        let walls = load("16 16\n16 16");
+        assert_eq!(
+            walls.validate(),
+            vec![MapIssue::AllWalls, MapIssue::NoSpawns]
+        );
+        assert_eq!(walls.spawns_count(), 0);
+
+        let no_spawns = load("0 0 16\n0 16 0");
+        assert_eq!(no_spawns.validate(), vec![MapIssue::NoSpawns]);
+        assert_eq!(no_spawns.spawns_count(), 4);
+        assert_eq!(no_spawns.regions().1, 2);
+
+        // Diagonal gaps don't count as connected
+        let unreachable = load("36 36 16 0\n0 0 16 0\n16 16 0 36");
+        let issue = MapIssue::UnreachableSpawns(vec![Vec2u::new(3, 2)]);
+        assert_eq!(unreachable.validate(), vec![issue]);
+
+        let bases = load("36 60 60 60");
+        assert_eq!(bases.validate(), vec![MapIssue::UnbalancedBases(3)]);
+    }
+
+    #[test]
+    fn test_meta() {
+        let meta = MapMeta::from_path("maps/Castle Islands (4).map");
+        assert_eq!(meta.name, "Castle Islands");
+        assert_eq!(meta.players, Some(4));
+        let meta = MapMeta::from_path("maps/extra/Snow (large).map");
+        assert_eq!(meta.name, "Snow (large)");
+        assert_eq!(meta.players, None);
+        assert_eq!(meta_path("maps/Atrium.map"), "maps/Atrium.meta");
+
+        let text = "# comment\r\nname  The  Map\nbots 3\nmodes ffa capture_the_cow\n\
+                    team_spawn 1 2 0\npickup shield 1 0\ncow_spawn 1 1\n\
+                    texture_list maps/tiles.txt\n";
+        let meta = load_meta(text, MapMeta::from_path("Old (2).map")).unwrap();
+        assert_eq!(meta.name, "The Map");
+        assert_eq!(meta.author, None);
+        assert_eq!(meta.players, Some(2));
+        assert_eq!(meta.bots, Some(3));
+        assert_eq!(meta.modes, vec![GameMode::Ffa, GameMode::CaptureTheCow]);
+        let team_spawn = TeamSpawn {
+            team: 1,
+            index: Vec2u::new(2, 0),
+        };
+        assert_eq!(meta.team_spawns, vec![team_spawn]);
+        assert_eq!(meta.pickups[0].name, "shield");
+        assert_eq!(meta.cow_spawns, vec![Vec2u::new(1, 1)]);
+        assert_eq!(meta.texture_list.as_deref(), Some("maps/tiles.txt"));
+
+        let err = load_meta("players 2\ncolor red", MapMeta::default()).unwrap_err();
+        let reason = ParseErrorReason::UnknownKey("color".to_owned());
+        assert_eq!(err, ParseError::new(2, 1, reason));
+
+        let err = load_meta("modes ffa tdm", MapMeta::default()).unwrap_err();
+        let reason = ParseErrorReason::InvalidMode("tdm".to_owned());
+        assert_eq!(err, ParseError::new(1, 11, reason));
+
+        let err = load_meta("cow_spawn 1", MapMeta::default()).unwrap_err();
+        let reason = ParseErrorReason::WrongFieldCount {
+            expected: 3,
+            found: 2,
+        };
+        assert_eq!(err, ParseError::new(1, 1, reason));
+
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+        let mut map = load_map("36 0 60\n0 16 0\n60 0 36", surfaces).unwrap();
+        let text = "modes capture_the_cow\nteam_spawn 0 0 0\nteam_spawn 1 1 0\n\
+                    team_spawn 2 2 2\npickup shield 1 1\ncow_spawn 5 5";
+        map.set_meta(load_meta(text, MapMeta::default()).unwrap());
+        assert_eq!(
+            map.validate(),
+            vec![
+                MapIssue::UnbalancedBases(2),
+                MapIssue::MisplacedEntity("team spawn", Vec2u::new(1, 0)),
+                MapIssue::MisplacedEntity("pickup", Vec2u::new(1, 1)),
+                MapIssue::MisplacedEntity("cow spawn", Vec2u::new(5, 5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_meta_files() {
+        let mut cnt = 0;
+        for entry in WalkDir::new("maps") {
+            let entry = entry.unwrap();
+            let path = entry.path().to_str().unwrap();
+            if entry.file_type().is_dir() || !path.ends_with(".meta") {
+                continue;
+            }
+
+            let meta_text = fs::read_to_string(path).unwrap();
+            if let Err(err) = load_meta(&meta_text, MapMeta::default()) {
+                panic!("{}: {}", path, err);
+            }
+            cnt += 1;
+        }
+        assert_ne!(cnt, 0);
+    }
+
+    #[test]
+    fn test_saving_maps() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+        for entry in WalkDir::new("maps") {
+            let entry = entry.unwrap();
+            let path = entry.path().to_str().unwrap();
+            if entry.file_type().is_dir() || !path.ends_with(".map") {
+                continue;
+            }
+
+            // All the maps we have use CRLF without a trailing newline so they should stay identical.
+            let map_text = fs::read_to_string(path).unwrap();
+            let map = load_map(&map_text, surfaces.clone()).unwrap();
+            assert_eq!(save_map(&map), map_text);
+        }
+    }
+
+    #[test]
+    fn test_editing_tiles() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+        // 0 is grass, 16 is wall, 36 is spawn, 60 is base
+        let mut map = load_map("36 0 60\n0 16 0", surfaces).unwrap();
+        map.set_meta(MapMeta::from_path("Edited (2).map"));
+
+        for rotation in 0..4 {
+            assert_eq!(Tile::new(9, rotation).rotation(), rotation);
+        }
+
+        map.set_tile(Vec2u::new(1, 1), Tile::new(9, 3));
+        map.set_tile(Vec2u::new(2, 0), Tile::new(0, 0));
+        assert_eq!(map.spawns(), &vec![Vec2u::new(0, 0), Vec2u::new(1, 1)]);
+        assert_eq!(map.bases().len(), 0);
+        assert_eq!(map.spawns_count(), 2);
+        assert_eq!(map.meta().players, Some(2));
+        assert_eq!(save_map(&map), "36 0 0\r\n0 39 0");
+    }
+
+    #[test]
+    fn test_spawn_fallbacks() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        let no_spawns = load_map("16 16 16\n16 0 16", surfaces.clone()).unwrap();
+        for _ in 0..10 {
+            let (pos, _) = no_spawns.random_spawn(&mut rng);
+            assert_eq!(pos, no_spawns.tile_center(Vec2u::new(1, 1)));
+        }
+
+        let walls = load_map("16 16\n16 16", surfaces).unwrap();
+        let (pos, _) = walls.random_spawn(&mut rng);
+        assert_eq!(pos, Vec2f::new(64.0, 64.0));
+    }
+
     #[test]
     fn test_map_a_simple_plan() {
         let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
-        let surfaces = load_tex_list(&tex_list_text);
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
         let map_text = fs::read_to_string("maps/A simple plan (2).map").unwrap();
-        let map = load_map(&map_text, surfaces);
+        let map = load_map(&map_text, surfaces).unwrap();
         assert_eq!(map.width(), 55);
         assert_eq!(map.height(), 23);
         assert_eq!(map.size(), Vec2u::new(55, 23));
@@ -444,14 +1286,22 @@ mod tests {
         assert_eq!(map.spawns()[0], Vec2u::new(9, 3));
         assert_eq!(map.bases().len(), 2);
         assert_eq!(map.bases()[0], Vec2u::new(10, 11));
+
+        let meta_text = fs::read_to_string("maps/A simple plan (2).meta").unwrap();
+        let meta = MapMeta::from_path("maps/A simple plan (2).map");
+        let mut map = map;
+        map.set_meta(load_meta(&meta_text, meta).unwrap());
+        assert_eq!(map.meta().players, Some(2));
+        assert_eq!(map.meta().team_spawns.len(), map.spawns().len());
+        assert_eq!(map.validate(), Vec::new());
     }
 
     #[test]
     fn test_collisions_between() {
         let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
-        let surfaces = load_tex_list(&tex_list_text);
+        let surfaces = load_tex_list(&tex_list_text).unwrap();
         let map_text = fs::read_to_string("maps/Corners (4).map").unwrap();
-        let map = load_map(&map_text, surfaces);
+        let map = load_map(&map_text, surfaces).unwrap();
 
         let outside = Vec2f::new(-50.0, -50.0);
 
@@ -0,0 +1,620 @@
+//! Procedural map generation.
+//!
+//! All random choices are made for a whole orbit of tiles (the tile and its symmetric copies)
+//! at once so the result is symmetric by construction.
+
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    mem,
+};
+
+use rand::{prelude::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumString};
+
+use crate::{
+    cvars::Cvars,
+    map::{GameMode, Map, MapMeta, Surface, TeamSpawn, Tile, Vec2u},
+};
+
+/// How the map is made fair for players starting in different places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+pub enum Symmetry {
+    /// Completely random, fair only by chance
+    None,
+    /// 2 players - left and right half, 4 players - also top and bottom
+    Mirror,
+    /// 2 players - rotated by 180°, 4 players - by 90° (only square maps)
+    Rotational,
+}
+
+/// Which surfaces are used for ground and walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+pub enum Biome {
+    Grass,
+    Desert,
+    Snow,
+    Ice,
+    /// Patches of all the others
+    Mixed,
+}
+
+impl Biome {
+    const ALL: [Biome; 4] = [Biome::Grass, Biome::Desert, Biome::Snow, Biome::Ice];
+
+    /// Names of surfaces from the texture list (ground variants, wall).
+    fn surface_names(self) -> (&'static [&'static str], &'static str) {
+        match self {
+            Biome::Grass => (&["g1", "g2", "g3"], "bunker1"),
+            Biome::Desert => (&["desert"], "d_rock"),
+            Biome::Snow => (&["snow", "snow2"], "bunker2"),
+            Biome::Ice => (&["ice1", "ice"], "bunker1"),
+            Biome::Mixed => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenParams {
+    pub seed: u64,
+    /// In tiles
+    pub width: usize,
+    /// In tiles
+    pub height: usize,
+    /// Approximate fraction of tiles which are walls
+    pub wall_density: f64,
+    pub symmetry: Symmetry,
+    /// Number of symmetric copies - only 2 or 4 are supported with symmetry
+    pub players: usize,
+    pub biome: Biome,
+    pub spawns_per_player: usize,
+    /// Give each player a base for Capture the Cow
+    pub bases: bool,
+}
+
+impl GenParams {
+    pub fn from_cvars(cvars: &Cvars, seed: u64) -> Self {
+        Self {
+            seed,
+            width: cvars.sv_mapgen_width,
+            height: cvars.sv_mapgen_height,
+            wall_density: cvars.sv_mapgen_wall_density,
+            symmetry: cvars.sv_mapgen_symmetry,
+            players: cvars.sv_mapgen_players,
+            biome: cvars.sv_mapgen_biome,
+            spawns_per_player: cvars.sv_mapgen_spawns_per_player,
+            bases: cvars.sv_mapgen_bases,
+        }
+    }
+}
+
+/// Size of the areas which share a biome in `Biome::Mixed`
+const BIOME_PATCH_SIZE: usize = 8;
+
+/// Generate a map from the given parameters.
+///
+/// All non-wall tiles (and therefore all spawns and bases) are guaranteed to be connected.
+/// The same parameters and `surfaces` always produce the same map.
+pub fn generate(params: &GenParams, surfaces: Vec<Surface>) -> Result<Map, String> {
+    let GenParams {
+        seed,
+        width,
+        height,
+        wall_density,
+        symmetry,
+        players,
+        biome,
+        spawns_per_player,
+        bases,
+    } = *params;
+    if width < 3 || height < 3 {
+        return Err(format!("Map size {}x{} is too small", width, height));
+    }
+    if symmetry != Symmetry::None && players != 2 && players != 4 {
+        return Err(format!("{} symmetry needs 2 or 4 players", symmetry));
+    }
+    if symmetry == Symmetry::Rotational && players == 4 && width != height {
+        return Err("Rotational symmetry for 4 players needs a square map".to_owned());
+    }
+    if players == 0 {
+        return Err("At least one player is needed".to_owned());
+    }
+
+    let surface = |name| {
+        surfaces
+            .iter()
+            .position(|surface| surface.name == name)
+            .ok_or_else(|| format!("Surface {} not found in the texture list", name))
+    };
+    let spawn_surface = surface("g_spawn")?;
+    let base_surface = surface("base")?;
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let orbits = Orbits {
+        width,
+        height,
+        symmetry,
+        players,
+    };
+
+    // Biomes and ground variants are chosen randomly per patch / tile,
+    // each tile then uses the choice of the first tile in its orbit.
+    let patches_x = (width + BIOME_PATCH_SIZE - 1) / BIOME_PATCH_SIZE;
+    let patches_y = (height + BIOME_PATCH_SIZE - 1) / BIOME_PATCH_SIZE;
+    let mut patch_biomes = vec![vec![biome; patches_x]; patches_y];
+    if biome == Biome::Mixed {
+        for patch in patch_biomes.iter_mut().flatten() {
+            *patch = Biome::ALL[rng.gen_range(0..Biome::ALL.len())];
+        }
+    }
+    let mut variants = vec![vec![0; width]; height];
+    for variant in variants.iter_mut().flatten() {
+        *variant = rng.gen::<usize>();
+    }
+
+    // Walls - random rectangular blocks until the density is reached.
+    let mut walls = vec![vec![false; width]; height];
+    let mut walls_count = 0;
+    let walls_target = (wall_density.clamp(0.0, 0.9) * (width * height) as f64) as usize;
+    let mut attempts = 0;
+    while walls_count < walls_target && attempts < width * height {
+        attempts += 1;
+        let block_w = rng.gen_range(1..=4.min(width));
+        let block_h = rng.gen_range(1..=4.min(height));
+        let x = rng.gen_range(0..=width - block_w);
+        let y = rng.gen_range(0..=height - block_h);
+        for r in y..y + block_h {
+            for c in x..x + block_w {
+                for index in orbits.of(Vec2u::new(c, r)) {
+                    if !walls[index.y][index.x] {
+                        walls[index.y][index.x] = true;
+                        walls_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Spawns and bases - one orbit gives one to each player.
+    let mut special = vec![vec![None; width]; height];
+    let mut team_spawns = Vec::new();
+    let spawn_orbits = if symmetry == Symmetry::None {
+        spawns_per_player * players
+    } else {
+        spawns_per_player
+    };
+    let base_orbits = match (bases, symmetry) {
+        (false, _) => 0,
+        // Without symmetry, players still need at least 2 bases to play CTC.
+        (true, Symmetry::None) => players.max(2),
+        (true, _) => 1,
+    };
+    let placements = [
+        ("spawns", spawn_surface, spawn_orbits),
+        ("bases", base_surface, base_orbits),
+    ];
+    for (name, surface_index, count) in placements {
+        let mut placed = 0;
+        let mut attempts = 0;
+        while placed < count && attempts < width * height {
+            attempts += 1;
+            let tile = Vec2u::new(rng.gen_range(0..width), rng.gen_range(0..height));
+            // Start from the canonical tile so the same team always gets the same part of the map.
+            let orbit = orbits.of(orbits.canonical(tile));
+            // Skip tiles on the axes of symmetry - they don't have distinct copies for each player.
+            let distinct = orbit
+                .iter()
+                .all(|&a| orbit.iter().filter(|&&b| a == b).count() == 1);
+            if !distinct
+                || orbit
+                    .iter()
+                    .any(|index| special[index.y][index.x].is_some())
+            {
+                continue;
+            }
+            for (team, &index) in orbit.iter().enumerate() {
+                special[index.y][index.x] = Some(surface_index);
+                walls[index.y][index.x] = false;
+                if surface_index == spawn_surface && symmetry != Symmetry::None {
+                    team_spawns.push(TeamSpawn { team, index });
+                }
+            }
+            placed += 1;
+        }
+        if placed < count {
+            let per_orbit = orbits.of(Vec2u::zero()).len();
+            return Err(format!(
+                "Only {} of {} {} fit on the map",
+                placed * per_orbit,
+                count * per_orbit,
+                name
+            ));
+        }
+    }
+
+    connect_regions(&orbits, &mut walls);
+
+    let mut tiles = Vec::with_capacity(height);
+    for r in 0..height {
+        let mut row = Vec::with_capacity(width);
+        for c in 0..width {
+            let canonical = orbits.canonical(Vec2u::new(c, r));
+            let biome =
+                patch_biomes[canonical.y / BIOME_PATCH_SIZE][canonical.x / BIOME_PATCH_SIZE];
+            let (grounds, wall) = biome.surface_names();
+            let tile = if let Some(surface_index) = special[r][c] {
+                // Face towards the center so players don't start looking at a wall.
+                let dx = c as f64 - (width - 1) as f64 / 2.0;
+                let dy = r as f64 - (height - 1) as f64 / 2.0;
+                let rotation = if dx.abs() >= dy.abs() {
+                    if dx > 0.0 {
+                        2
+                    } else {
+                        0
+                    }
+                } else if dy > 0.0 {
+                    1
+                } else {
+                    3
+                };
+                Tile::new(surface_index, rotation)
+            } else if walls[r][c] {
+                Tile::new(surface(wall)?, 0)
+            } else {
+                let variant = variants[canonical.y][canonical.x] % grounds.len();
+                Tile::new(surface(grounds[variant])?, 0)
+            };
+            row.push(tile);
+        }
+        tiles.push(row);
+    }
+
+    let mut map = Map::new(tiles, surfaces);
+    let mut modes = vec![GameMode::Ffa];
+    if players >= 2 {
+        modes.push(GameMode::TeamWar);
+    }
+    if map.bases().len() >= 2 {
+        modes.push(GameMode::CaptureTheCow);
+    }
+    map.set_meta(MapMeta {
+        name: format!("Generated {}", seed),
+        players: Some(players),
+        modes,
+        team_spawns,
+        ..MapMeta::default()
+    });
+    Ok(map)
+}
+
+/// Turn walls into ground until all non-wall tiles are connected.
+///
+/// Each disconnected region gets a path dug to the nearest tile of the largest region.
+/// Digging is done on whole orbits to keep the symmetry.
+fn connect_regions(orbits: &Orbits, walls: &mut [Vec<bool>]) {
+    let width = orbits.width;
+    let height = orbits.height;
+
+    // Flood fill once like Map::regions but on the bool grid,
+    // then merge the regions as paths between them are dug.
+    let mut labels = vec![vec![None; width]; height];
+    let mut regions = Regions::default();
+    for r in 0..height {
+        for c in 0..width {
+            if walls[r][c] || labels[r][c].is_some() {
+                continue;
+            }
+            let mut tiles = vec![Vec2u::new(c, r)];
+            let region = regions.add(Vec::new());
+            labels[r][c] = Some(region);
+            let mut stack = vec![Vec2u::new(c, r)];
+            while let Some(index) = stack.pop() {
+                for n in neighbors(index, width, height) {
+                    if !walls[n.y][n.x] && labels[n.y][n.x].is_none() {
+                        labels[n.y][n.x] = Some(region);
+                        tiles.push(n);
+                        stack.push(n);
+                    }
+                }
+            }
+            regions.tiles[region] = tiles;
+        }
+    }
+
+    loop {
+        let roots = regions.roots();
+        if roots.len() <= 1 {
+            return;
+        }
+        let main_region = *roots
+            .iter()
+            .max_by_key(|&&root| regions.tiles[root].len())
+            .unwrap();
+        // Start from the smallest region so the search stays small.
+        let other_region = *roots
+            .iter()
+            .filter(|&&root| root != main_region)
+            .min_by_key(|&&root| regions.tiles[root].len())
+            .unwrap();
+
+        // BFS through everything from the other region until we hit the main region.
+        let mut prev = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &index in &regions.tiles[other_region] {
+            prev.insert(index, index);
+            queue.push_back(index);
+        }
+        let mut end = None;
+        while let Some(index) = queue.pop_front() {
+            if let Some(label) = labels[index.y][index.x] {
+                if regions.find(label) == main_region {
+                    end = Some(index);
+                    break;
+                }
+            }
+            for n in neighbors(index, width, height) {
+                if let Entry::Vacant(entry) = prev.entry(n) {
+                    entry.insert(index);
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        // Dig the path back. Each dug tile starts as its own region,
+        // then it's merged with whatever it touches - the copies in other orbits
+        // can connect different regions than the path itself.
+        let mut dug = Vec::new();
+        let mut index = end.unwrap();
+        while prev[&index] != index {
+            for orbit_index in orbits.of(index) {
+                if walls[orbit_index.y][orbit_index.x] {
+                    walls[orbit_index.y][orbit_index.x] = false;
+                    labels[orbit_index.y][orbit_index.x] = Some(regions.add(vec![orbit_index]));
+                    dug.push(orbit_index);
+                }
+            }
+            index = prev[&index];
+        }
+        for index in dug {
+            let label = labels[index.y][index.x].unwrap();
+            for n in neighbors(index, width, height) {
+                if let Some(neighbor_label) = labels[n.y][n.x] {
+                    regions.union(label, neighbor_label);
+                }
+            }
+        }
+    }
+}
+
+/// Union-find of connected regions of ground tiles.
+#[derive(Debug, Clone, Default)]
+struct Regions {
+    parents: Vec<usize>,
+    /// All tiles of the region, only kept for roots.
+    tiles: Vec<Vec<Vec2u>>,
+}
+
+impl Regions {
+    fn add(&mut self, tiles: Vec<Vec2u>) -> usize {
+        let region = self.parents.len();
+        self.parents.push(region);
+        self.tiles.push(tiles);
+        region
+    }
+
+    fn find(&mut self, mut region: usize) -> usize {
+        while self.parents[region] != region {
+            // Path halving
+            self.parents[region] = self.parents[self.parents[region]];
+            region = self.parents[region];
+        }
+        region
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        // Move the smaller list into the bigger one.
+        if self.tiles[a].len() < self.tiles[b].len() {
+            mem::swap(&mut a, &mut b);
+        }
+        let tiles = mem::take(&mut self.tiles[b]);
+        self.tiles[a].extend(tiles);
+        self.parents[b] = a;
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        (0..self.parents.len())
+            .filter(|&region| self.parents[region] == region)
+            .collect()
+    }
+}
+
+/// Up to 4 neighbors of a tile, diagonals are not connected.
+fn neighbors(index: Vec2u, width: usize, height: usize) -> Vec<Vec2u> {
+    let mut ret = Vec::with_capacity(4);
+    if index.x > 0 {
+        ret.push(Vec2u::new(index.x - 1, index.y));
+    }
+    if index.x + 1 < width {
+        ret.push(Vec2u::new(index.x + 1, index.y));
+    }
+    if index.y > 0 {
+        ret.push(Vec2u::new(index.x, index.y - 1));
+    }
+    if index.y + 1 < height {
+        ret.push(Vec2u::new(index.x, index.y + 1));
+    }
+    ret
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Orbits {
+    width: usize,
+    height: usize,
+    symmetry: Symmetry,
+    players: usize,
+}
+
+impl Orbits {
+    /// The tile and all its symmetric copies, one per player (can contain duplicates on the axes).
+    fn of(&self, index: Vec2u) -> Vec<Vec2u> {
+        let (c, r) = (index.x, index.y);
+        let (w, h) = (self.width, self.height);
+        match (self.symmetry, self.players) {
+            (Symmetry::None, _) => vec![index],
+            (Symmetry::Mirror, 2) => vec![index, Vec2u::new(w - 1 - c, r)],
+            (Symmetry::Mirror, _) => vec![
+                index,
+                Vec2u::new(w - 1 - c, r),
+                Vec2u::new(c, h - 1 - r),
+                Vec2u::new(w - 1 - c, h - 1 - r),
+            ],
+            (Symmetry::Rotational, 2) => vec![index, Vec2u::new(w - 1 - c, h - 1 - r)],
+            (Symmetry::Rotational, _) => vec![
+                index,
+                Vec2u::new(w - 1 - r, c),
+                Vec2u::new(w - 1 - c, h - 1 - r),
+                Vec2u::new(r, h - 1 - c),
+            ],
+        }
+    }
+
+    /// A tile which represents the whole orbit - the same for all tiles in the orbit.
+    ///
+    /// Its orbit is ordered so that the N-th tile is always in the same part of the map,
+    /// e.g. for `Mirror` the first is always in the left half.
+    fn canonical(&self, index: Vec2u) -> Vec2u {
+        let orbit = self.of(index);
+        if self.symmetry == Symmetry::Rotational && self.players == 4 {
+            // The topmost tile could be in either of the top quadrants, use the top left one.
+            let in_quadrant =
+                |index: &&Vec2u| index.x < (self.width + 1) / 2 && index.y < self.height / 2;
+            if let Some(&index) = orbit.iter().find(in_quadrant) {
+                return index;
+            }
+        }
+        orbit
+            .into_iter()
+            .min_by_key(|index| (index.y, index.x))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::map::{self, Kind, MapIssue};
+
+    fn surfaces() -> Vec<Surface> {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        map::load_tex_list(&tex_list_text).unwrap()
+    }
+
+    fn params(seed: u64, symmetry: Symmetry, players: usize) -> GenParams {
+        GenParams {
+            seed,
+            width: 30,
+            height: 30,
+            wall_density: 0.3,
+            symmetry,
+            players,
+            biome: Biome::Mixed,
+            spawns_per_player: 5,
+            bases: true,
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let map1 = generate(&params(7, Symmetry::Mirror, 2), surfaces()).unwrap();
+        let map2 = generate(&params(7, Symmetry::Mirror, 2), surfaces()).unwrap();
+        let map3 = generate(&params(8, Symmetry::Mirror, 2), surfaces()).unwrap();
+        assert_eq!(map::save_map(&map1), map::save_map(&map2));
+        assert_ne!(map::save_map(&map1), map::save_map(&map3));
+    }
+
+    #[test]
+    fn test_symmetric_and_connected() {
+        let combinations = [
+            (Symmetry::None, 1),
+            (Symmetry::None, 3),
+            (Symmetry::Mirror, 2),
+            (Symmetry::Mirror, 4),
+            (Symmetry::Rotational, 2),
+            (Symmetry::Rotational, 4),
+        ];
+        for (symmetry, players) in combinations {
+            for seed in 0..10 {
+                let params = params(seed, symmetry, players);
+                let map = generate(&params, surfaces()).unwrap();
+
+                assert_eq!(map.regions().1, 1, "{} {} {}", symmetry, players, seed);
+                for issue in map.validate() {
+                    // Without symmetry, bases are placed for each player.
+                    assert_eq!(issue, MapIssue::UnbalancedBases(3));
+                }
+                assert_eq!(map.spawns().len(), params.spawns_per_player * players);
+
+                let orbits = Orbits {
+                    width: params.width,
+                    height: params.height,
+                    symmetry,
+                    players,
+                };
+                for r in 0..map.height() {
+                    for c in 0..map.width() {
+                        let kind = map.surface_at_index(Vec2u::new(c, r)).kind;
+                        for index in orbits.of(Vec2u::new(c, r)) {
+                            assert_eq!(map.surface_at_index(index).kind, kind);
+                        }
+                    }
+                }
+
+                // Each team gets the same part of the map for all its spawns.
+                for team_spawn in &map.meta().team_spawns {
+                    let index = team_spawn.index;
+                    assert_eq!(map.surface_at_index(index).kind, Kind::Spawn);
+                    if symmetry == Symmetry::Mirror {
+                        let right = index.x >= params.width / 2;
+                        assert_eq!(team_spawn.team % 2 == 1, right);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large() {
+        let params = GenParams {
+            width: 200,
+            height: 150,
+            spawns_per_player: 50,
+            ..params(1, Symmetry::Rotational, 2)
+        };
+        let map = generate(&params, surfaces()).unwrap();
+        assert_eq!(map.size(), Vec2u::new(200, 150));
+        assert_eq!(map.regions().1, 1);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let mut rotational = params(0, Symmetry::Rotational, 4);
+        rotational.height = 20;
+        assert!(generate(&rotational, surfaces()).is_err());
+        assert!(generate(&params(0, Symmetry::Mirror, 3), surfaces()).is_err());
+        assert!(generate(&params(0, Symmetry::None, 0), surfaces()).is_err());
+
+        let crowded = GenParams {
+            width: 3,
+            height: 3,
+            ..params(0, Symmetry::Mirror, 2)
+        };
+        let err = generate(&crowded, surfaces()).unwrap_err();
+        assert!(err.contains("spawns"), "{}", err);
+    }
+}
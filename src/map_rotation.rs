@@ -0,0 +1,121 @@
+//! Which maps are offered in the vote at the end of a match.
+
+use rand::{prelude::SmallRng, seq::SliceRandom, SeedableRng};
+
+#[derive(Debug, Clone)]
+pub struct MapRotation {
+    /// Map paths
+    maps: Vec<String>,
+    /// Index of the map being played if it's in the rotation
+    current: Option<usize>,
+    /// Separate from the gamelogic RNG so the votes don't affect gameplay.
+    rng: SmallRng,
+}
+
+impl MapRotation {
+    pub fn new(maps: Vec<String>, seed: u64) -> Self {
+        Self {
+            maps,
+            current: None,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Switch to a different list of maps, e.g. after `sv_map_rotation` changed.
+    ///
+    /// `current` is the map being played, it might not have been in the old list.
+    pub fn set_maps(&mut self, maps: Vec<String>, current: &str) {
+        self.maps = maps;
+        self.set_current(current);
+    }
+
+    /// Remember which map is being played so the one after it is offered first.
+    pub fn set_current(&mut self, map: &str) {
+        self.current = self.maps.iter().position(|m| m == map);
+    }
+
+    pub fn map_count(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// The map after the current one, the first one if the current map is not in rotation.
+    pub fn next(&self) -> Option<String> {
+        self.next_index().map(|next| self.maps[next].clone())
+    }
+
+    fn next_index(&self) -> Option<usize> {
+        if self.maps.is_empty() {
+            return None;
+        }
+        Some(
+            self.current
+                .map_or(0, |current| (current + 1) % self.maps.len()),
+        )
+    }
+
+    /// The next map in rotation followed by random other maps.
+    ///
+    /// Doesn't offer the current map unless there's nothing else.
+    pub fn vote_options(&mut self, count: usize) -> Vec<String> {
+        let next = match self.next_index() {
+            Some(next) => next,
+            None => return Vec::new(),
+        };
+        let mut options = vec![self.maps[next].clone()];
+
+        let mut others: Vec<_> = (0..self.maps.len())
+            .filter(|&i| i != next && Some(i) != self.current)
+            .collect();
+        others.shuffle(&mut self.rng);
+        for i in others.into_iter().take(count.saturating_sub(1)) {
+            options.push(self.maps[i].clone());
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(names: &[&str]) -> MapRotation {
+        let maps = names.iter().map(|&name| name.to_owned()).collect();
+        MapRotation::new(maps, 0)
+    }
+
+    #[test]
+    fn test_vote_options() {
+        let mut rot = rotation(&["a", "b", "c", "d"]);
+        assert_eq!(rot.vote_options(1), vec!["a"]);
+        rot.set_current("c");
+        assert_eq!(rot.next().unwrap(), "d");
+        rot.set_current("not in rotation");
+        assert_eq!(rot.next().unwrap(), "a");
+
+        rot.set_current("b");
+        let options = rot.vote_options(3);
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0], "c");
+        assert!(!options.contains(&"b".to_owned()));
+
+        rot.set_current("d");
+        let mut options = rot.vote_options(10);
+        assert_eq!(options[0], "a");
+        options.sort();
+        assert_eq!(options, vec!["a", "b", "c"]);
+
+        // The map being played is only offered if there's nothing else.
+        let mut single = rotation(&["a"]);
+        single.set_current("a");
+        assert_eq!(single.vote_options(3), vec!["a"]);
+
+        // The map being played can become part of the new list.
+        let mut rot = rotation(&["a", "b"]);
+        rot.set_current("x");
+        rot.set_maps(vec!["x".to_owned(), "y".to_owned()], "x");
+        assert_eq!(rot.vote_options(3), vec!["y"]);
+
+        assert!(rotation(&[]).vote_options(3).is_empty());
+        assert!(rotation(&[]).next().is_none());
+    }
+}
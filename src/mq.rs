@@ -1,21 +1,28 @@
 //! Native and WASM versions using the macroquad engine.
 
+use std::mem;
+
 use cvars_console_macroquad::MacroquadConsole;
 use futures::future;
 use macroquad::prelude::*;
 use thunderdome::Index;
+use vek::Clamp;
 
 use crate::{
-    cvars::Cvars,
+    cvars::{Cvars, LookAhead, SplitscreenLayout},
     game_state::Input,
-    map::Vec2f,
-    server::Server,
+    gamepad::Gamepads,
+    map::{F64Ext, Surface, Vec2f, VecExt, TILE_SIZE},
+    server::{MatchState, Server},
     timing::{Durations, Fps},
 };
 
 #[derive(Debug)]
 pub struct MacroquadClient {
+    /// Indexed by `Tile::surface_index`
     pub imgs_tiles: Vec<Texture2D>,
+    /// Images `imgs_tiles` were loaded from
+    pub paths_tiles: Vec<String>,
     pub imgs_vehicles: Vec<Texture2D>,
     pub imgs_wrecks: Vec<Texture2D>,
     pub imgs_weapon_icons: Vec<Texture2D>,
@@ -27,10 +34,23 @@ pub struct MacroquadClient {
     pub render_fps: Fps,
     pub render_cmds_durations: Durations,
     pub rest_durations: Durations,
+    /// Whether `r_fullscreen` has been applied to the window
+    pub fullscreen: bool,
+    /// Screen size, `r_splitscreen_layout` and `r_splitscreen_gap` the viewports were laid out for
+    pub layout_params: (Vec2f, SplitscreenLayout, f64),
     pub viewport_size: Vec2f,
+    /// Add to world coords to get zoomed screen coords in player 1's viewport.
+    ///
+    /// Saved during rendering so mouse aiming uses the same camera the player sees.
+    pub camera_offset: Vec2f,
+    /// One for each local player in the order of their binds
+    pub cameras: Vec<CameraState>,
     pub client_mode: ClientMode,
     pub last_key: Option<KeyCode>,
     pub console: MacroquadConsole,
+    pub gamepads: Gamepads,
+    /// Down input of each local player last frame, so holding a button only changes the vote once.
+    pub vote_input_prev: Vec<Input>,
 }
 
 #[derive(Debug)]
@@ -38,40 +58,141 @@ pub enum ClientMode {
     Singleplayer {
         player_handle: Index,
     },
+    /// Up to `binds::MAX_LOCAL_PLAYERS`, each with their own part of the screen.
     Splitscreen {
-        render_targets: (RenderTarget, RenderTarget),
-        player_handles: (Index, Index),
+        viewports: Vec<Viewport>,
     },
 }
 
+/// One local player's part of the screen in splitscreen.
+#[derive(Debug)]
+pub struct Viewport {
+    pub player_handle: Index,
+    pub render_target: RenderTarget,
+    /// Top left corner in screen coords
+    pub pos: Vec2f,
+}
+
+/// Zoom and look-ahead of one local player's camera.
+///
+/// They move toward the values wanted by cvars over time instead of jumping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraState {
+    /// Screen pixels per world pixel
+    pub zoom: f64,
+    /// Shift from the followed entity in world coords
+    pub look_ahead: Vec2f,
+}
+
+impl CameraState {
+    pub fn new(zoom: f64) -> Self {
+        Self {
+            zoom,
+            look_ahead: Vec2f::zero(),
+        }
+    }
+
+    /// Move toward the wanted zoom and look-ahead by the amount of time since the last frame.
+    pub fn update(&mut self, cvars: &Cvars, zoom: f64, look_ahead: Vec2f, dt: f64) {
+        self.zoom = approach(self.zoom, zoom, cvars.cl_zoom_speed, dt);
+        self.look_ahead = approach(
+            self.look_ahead,
+            look_ahead,
+            cvars.cl_camera_look_ahead_speed,
+            dt,
+        );
+    }
+}
+
+/// Exponential smoothing - covers a fixed fraction of the remaining distance per unit of time
+/// so it looks the same at any framerate.
+fn approach<T>(current: T, wanted: T, speed: f64, dt: f64) -> T
+where
+    T: Copy
+        + std::ops::Add<Output = T>
+        + std::ops::Sub<Output = T>
+        + std::ops::Mul<f64, Output = T>,
+{
+    if speed == 0.0 {
+        return wanted;
+    }
+    let fraction = 1.0 - (-speed * dt).exp();
+    current + (wanted - current) * fraction
+}
+
+/// Zoom wanted by cvars - `cl_zoom` adjusted by `cl_zoom_auto`.
+pub fn wanted_zoom(cvars: &Cvars, map_size: Vec2f) -> f64 {
+    if !cvars.cl_zoom_auto {
+        return cvars.cl_zoom;
+    }
+    let tiles = map_size.x.max(map_size.y) / TILE_SIZE;
+    let auto = (cvars.cl_zoom_auto_tiles / tiles).clamp(cvars.cl_zoom_auto_min.min(1.0), 1.0);
+    cvars.cl_zoom * auto
+}
+
+/// Look-ahead wanted by cvars for the given player, zero if the camera follows a guided missile.
+pub fn wanted_look_ahead(cvars: &Cvars, server: &Server, player_handle: Index) -> Vec2f {
+    let player = &server.gs.players[player_handle];
+    let vehicle_handle = match player.vehicle {
+        Some(handle) if player.guided_missile.is_none() => handle,
+        _ => return Vec2f::zero(),
+    };
+    let vehicle = &server.gs.vehicles[vehicle_handle];
+    let distance = cvars.cl_camera_look_ahead_distance;
+    match cvars.cl_camera_look_ahead {
+        LookAhead::Off => Vec2f::zero(),
+        LookAhead::Velocity => {
+            let ahead = vehicle.vel * cvars.cl_camera_look_ahead_time;
+            if ahead.magnitude() > distance {
+                ahead.normalized() * distance
+            } else {
+                ahead
+            }
+        }
+        LookAhead::Turret => (vehicle.angle + vehicle.turret_angle_current).to_vec2f() * distance,
+    }
+}
+
+/// The part of the map a viewport shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// Top left corner of the visible part of the map in world coords
+    pub camera_top_left: Vec2f,
+    /// Top left corner of the map's visible part in screen coords.
+    ///
+    /// Nonzero if the map is smaller than the viewport,
+    /// the map is then centered and the empty space around it is black.
+    pub pos: Vec2f,
+    /// Size of the map's visible part in screen coords
+    pub size: Vec2f,
+}
+
+impl View {
+    /// Center the view on `center` (in world coords) but don't put it so close to the edge
+    /// that it would show area outside the map.
+    ///
+    /// Maps smaller than the view (along X, Y or both) are centered instead.
+    /// Example maps for testing: Joust (2), extra/OK Corral (2)
+    pub fn new(viewport_size: Vec2f, map_size: Vec2f, zoom: f64, center: Vec2f) -> Self {
+        // In world coords
+        let visible_size = (viewport_size / zoom).map2(map_size, f64::min);
+        let center_min = visible_size / 2.0;
+        let center_max = map_size - center_min;
+        let camera_center = center.clamped(center_min, center_max);
+
+        let size = visible_size * zoom;
+        Self {
+            camera_top_left: camera_center - center_min,
+            pos: (viewport_size - size) / 2.0,
+            size,
+        }
+    }
+}
+
 impl MacroquadClient {
-    pub async fn new(cvars: &Cvars, player1_handle: Index, player2_handle: Option<Index>) -> Self {
+    pub async fn new(cvars: &Cvars, surfaces: &[Surface], player_handles: Vec<Index>) -> Self {
         let loading_started = get_time();
 
-        let paths_tiles = [
-            "assets/tiles/g1.bmp",
-            "assets/tiles/g2.bmp",
-            "assets/tiles/g3.bmp",
-            "assets/tiles/g_stripes.bmp",
-            "assets/tiles/bunker1.bmp",
-            "assets/tiles/ice1.bmp",
-            "assets/tiles/ice.bmp",
-            "assets/tiles/ice_side.bmp",
-            "assets/tiles/ice_corner.bmp",
-            "assets/tiles/g_spawn.bmp",
-            "assets/tiles/road.bmp",
-            "assets/tiles/water.bmp",
-            "assets/tiles/snow.bmp",
-            "assets/tiles/snow2.bmp",
-            "assets/tiles/bunker2.bmp",
-            "assets/tiles/base.bmp",
-            "assets/tiles/water_side.bmp",
-            "assets/tiles/water_corner.bmp",
-            "assets/tiles/desert.bmp",
-            "assets/tiles/d_rock.bmp",
-            "assets/tiles/g2d.bmp",
-            "assets/tiles/water_middle.bmp",
-        ];
         let paths_vehicles = [
             "assets/vehicles/tank_chassis_flames.png",
             "assets/vehicles/tank_turret_flames.png",
@@ -101,8 +222,8 @@ impl MacroquadClient {
             "assets/explosion.png",
             "assets/explosion_cyan.png",
         ];
+        let imgs_tiles = load_tiles(surfaces).await;
         let paths = [
-            &paths_tiles[..],
             &paths_vehicles[..],
             &paths_wrecks[..],
             &paths_weapon_icons[..],
@@ -121,7 +242,6 @@ impl MacroquadClient {
         .into_iter();
         draw_text("Loading...", 400.0, 400.0, 32.0, WHITE);
 
-        let imgs_tiles = textures.by_ref().take(paths_tiles.len()).collect();
         let imgs_vehicles = textures.by_ref().take(paths_vehicles.len()).collect();
         let imgs_wrecks = textures.by_ref().take(paths_wrecks.len()).collect();
         let imgs_weapon_icons = textures.by_ref().take(paths_weapon_icons.len()).collect();
@@ -145,30 +265,30 @@ impl MacroquadClient {
             screen_width(),
             screen_height()
         );
-        let (viewport_size, client_mode) = if let Some(player2_handle) = player2_handle {
-            let viewport_width = (screen_width() as f64 - cvars.r_splitscreen_gap) / 2.0;
-            let viewport_size = Vec2f::new(viewport_width, screen_height() as f64);
-            let viewport_left = render_target(viewport_size.x as u32, viewport_size.y as u32);
-            let viewport_right = render_target(viewport_size.x as u32, viewport_size.y as u32);
-
-            let client_mode = ClientMode::Splitscreen {
-                render_targets: (viewport_left, viewport_right),
-                player_handles: (player1_handle, player2_handle),
-            };
-
-            (viewport_size, client_mode)
+        let screen_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
+        let local_players = player_handles.len();
+        let (viewport_size, client_mode) = if local_players > 1 {
+            let layout = splitscreen_layout(cvars, local_players, screen_size);
+            let viewports = player_handles
+                .into_iter()
+                .enumerate()
+                .map(|(i, player_handle)| Viewport {
+                    player_handle,
+                    render_target: new_render_target(layout.viewport_size),
+                    pos: layout.viewport_pos(cvars, i),
+                })
+                .collect();
+            (layout.viewport_size, ClientMode::Splitscreen { viewports })
         } else {
-            let viewport_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
-
             let client_mode = ClientMode::Singleplayer {
-                player_handle: player1_handle,
+                player_handle: player_handles[0],
             };
-
-            (viewport_size, client_mode)
+            (screen_size, client_mode)
         };
 
         Self {
             imgs_tiles,
+            paths_tiles: tile_paths(surfaces),
             imgs_vehicles,
             imgs_wrecks,
             imgs_weapon_icons,
@@ -180,154 +300,474 @@ impl MacroquadClient {
             render_fps: Fps::new(),
             render_cmds_durations: Durations::new(),
             rest_durations: Durations::new(),
+            fullscreen: false,
+            layout_params: (
+                screen_size,
+                cvars.r_splitscreen_layout,
+                cvars.r_splitscreen_gap,
+            ),
             viewport_size,
+            camera_offset: Vec2f::zero(),
+            cameras: vec![CameraState::new(cvars.cl_zoom); local_players],
             client_mode,
             last_key: None,
             console: MacroquadConsole::new(),
+            gamepads: Gamepads::new(),
+            vote_input_prev: Vec::new(),
+        }
+    }
+
+    /// Load tile textures for a different tileset, e.g. after switching to a map with custom surfaces.
+    ///
+    /// Does nothing if the images are the same as the current ones.
+    pub async fn set_tileset(&mut self, surfaces: &[Surface]) {
+        let paths = tile_paths(surfaces);
+        if paths != self.paths_tiles {
+            let old = mem::replace(&mut self.imgs_tiles, load_tiles(surfaces).await);
+            // Textures are not freed when dropped.
+            for img in old {
+                img.delete();
+            }
+            self.paths_tiles = paths;
+        }
+    }
+
+    /// Apply `r_fullscreen` and lay out the viewports again if the window was resized
+    /// or the layout cvars changed. HUD positions are relative to the viewports so they follow.
+    pub fn update_window(&mut self, cvars: &Cvars) {
+        if self.fullscreen != cvars.r_fullscreen {
+            // Can't use `Conf::fullscreen` because of https://github.com/not-fl3/macroquad/issues/237.
+            // SAFETY: Only used to change the window mode, not to mess with macroquad's rendering state.
+            unsafe {
+                get_internal_gl()
+                    .quad_context
+                    .set_fullscreen(cvars.r_fullscreen);
+            }
+            self.fullscreen = cvars.r_fullscreen;
+        }
+
+        let screen_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
+        let layout_params = (
+            screen_size,
+            cvars.r_splitscreen_layout,
+            cvars.r_splitscreen_gap,
+        );
+        if layout_params == self.layout_params {
+            return;
+        }
+        self.layout_params = layout_params;
+
+        match &mut self.client_mode {
+            ClientMode::Singleplayer { .. } => self.viewport_size = screen_size,
+            ClientMode::Splitscreen { viewports } => {
+                let layout = splitscreen_layout(cvars, viewports.len(), screen_size);
+                let resized = layout.viewport_size != self.viewport_size;
+                for (i, viewport) in viewports.iter_mut().enumerate() {
+                    if resized {
+                        // Render targets are not freed when dropped,
+                        // without this, memory would grow with every resize.
+                        viewport.render_target.delete();
+                        viewport.render_target = new_render_target(layout.viewport_size);
+                    }
+                    viewport.pos = layout.viewport_pos(cvars, i);
+                }
+                self.viewport_size = layout.viewport_size;
+            }
         }
     }
 
-    pub fn process_input(&mut self, server: &mut Server) {
+    /// Move each local player's camera toward what the cvars want.
+    pub fn update_cameras(&mut self, server: &Server, cvars: &Cvars, dt: f64) {
+        let zoom = wanted_zoom(cvars, server.map.maxs());
+        let player_handles = self.player_handles();
+        for (camera, handle) in self.cameras.iter_mut().zip(player_handles) {
+            let look_ahead = wanted_look_ahead(cvars, server, handle);
+            camera.update(cvars, zoom, look_ahead, dt);
+        }
+    }
+
+    /// Use new player handles, e.g. after restarting the server with an edited map.
+    pub fn set_player_handles(&mut self, player_handles: Vec<Index>) {
+        match &mut self.client_mode {
+            ClientMode::Singleplayer { player_handle } => *player_handle = player_handles[0],
+            ClientMode::Splitscreen { viewports } => {
+                for (viewport, handle) in viewports.iter_mut().zip(player_handles) {
+                    viewport.player_handle = handle;
+                }
+            }
+        }
+    }
+
+    /// Handle of each local player in the order of their binds.
+    pub fn player_handles(&self) -> Vec<Index> {
+        match &self.client_mode {
+            ClientMode::Singleplayer { player_handle } => vec![*player_handle],
+            ClientMode::Splitscreen { viewports } => viewports
+                .iter()
+                .map(|viewport| viewport.player_handle)
+                .collect(),
+        }
+    }
+
+    pub fn process_input(&mut self, server: &mut Server, cvars: &Cvars, real_time: f64) {
         if self.console.is_open() {
             return;
         }
 
-        let input1 = get_input1();
-        let input2 = get_input2();
+        // Gamepads are polled once per frame so there's no difference between pressed and down.
+        self.gamepads.poll();
 
-        match self.client_mode {
+        // Pressed includes keys which were pressed and released since the last frame.
+        let mut pressed: Vec<_> = cvars
+            .cl_binds
+            .iter()
+            .map(|binds| binds.input(was_input_pressed))
+            .collect();
+        let mut down: Vec<_> = cvars
+            .cl_binds
+            .iter()
+            .map(|binds| binds.input(is_key_down))
+            .collect();
+        let (mouse_pressed, mouse_down) = self.mouse_input(server, cvars);
+        pressed[0] = pressed[0].merged(mouse_pressed);
+        down[0] = down[0].merged(mouse_down);
+
+        // Handle, pressed and down input of each local player.
+        let inputs: Vec<_> = match &self.client_mode {
             ClientMode::Singleplayer { player_handle } => {
-                let input = input1.merged(input2);
-                server.input(player_handle, input);
-            }
-            ClientMode::Splitscreen {
-                render_targets: _,
-                player_handles: (player1_handle, player2_handle),
-            } => {
-                server.input(player1_handle, input1);
-                server.input(player2_handle, input2);
+                // All the binds control the only player.
+                let gamepad = self.gamepad_input(cvars, 0);
+                let merge =
+                    |inputs: Vec<Input>| inputs.into_iter().fold(gamepad, |a, b| a.merged(b));
+                vec![(*player_handle, merge(pressed), merge(down))]
             }
+            ClientMode::Splitscreen { viewports } => viewports
+                .iter()
+                .enumerate()
+                .map(|(i, viewport)| {
+                    let gamepad = self.gamepad_input(cvars, i);
+                    let pressed = pressed[i].merged(gamepad);
+                    let down = down[i].merged(gamepad);
+                    (viewport.player_handle, pressed, down)
+                })
+                .collect(),
+        };
+
+        if let MatchState::Voting(_) = server.match_state {
+            self.process_vote(server, &inputs);
+        }
+        self.vote_input_prev = inputs.iter().map(|&(_, _, down)| down).collect();
+
+        for (handle, pressed, down) in inputs {
+            send_input(server, cvars, handle, real_time, pressed, down);
         }
 
         if let Some(key_code) = get_last_key_pressed() {
             self.last_key = Some(key_code);
         }
     }
-}
 
-// Keys to avoid in defaults:
-//  - Ctrl - ctrl+W closes the browser tab
-//  - Alt - shows/hides the firefox menu bar on linux
-//  - Numpad - Some keyboards might not have it
-//  - Keys that often depend on layout - https://github.com/not-fl3/macroquad/issues/260
-// LATER Configurable input
-
-fn get_input1() -> Input {
-    let mut input = Input::new();
-    if was_input_pressed(&[KeyCode::A]) {
-        input.left = true;
-    }
-    if was_input_pressed(&[KeyCode::D]) {
-        input.right = true;
-    }
-    if was_input_pressed(&[KeyCode::W]) {
-        input.up = true;
-    }
-    if was_input_pressed(&[KeyCode::S]) {
-        input.down = true;
-    }
-    if was_input_pressed(&[KeyCode::Q]) {
-        input.turret_left = true;
-    }
-    if was_input_pressed(&[KeyCode::E]) {
-        input.turret_right = true;
-    }
-    if was_input_pressed(&[KeyCode::V]) {
-        input.prev_weapon = true;
-    }
-    if was_input_pressed(&[KeyCode::LeftShift, KeyCode::C]) {
-        input.next_weapon = true;
-    }
-    if was_input_pressed(&[KeyCode::Space]) {
-        input.fire = true;
+    /// Input from the gamepad assigned to the i-th local player.
+    ///
+    /// Looked up every frame so changing `cl_gamepad_player*` applies immediately.
+    fn gamepad_input(&self, cvars: &Cvars, i: usize) -> Input {
+        let index = gamepad_index(cvars.cl_gamepad_player(i));
+        self.gamepads.input(cvars, index)
     }
-    if was_input_pressed(&[KeyCode::X]) {
-        input.mine = true;
-    }
-    if was_input_pressed(&[KeyCode::G]) {
-        input.self_destruct = true;
-    }
-    if was_input_pressed(&[KeyCode::R]) {
-        input.horn = true;
+
+    /// Mouse aiming for player 1 - the turret points at the cursor, the left button fires.
+    ///
+    /// Returns pressed and down input like the keyboard.
+    fn mouse_input(&self, server: &Server, cvars: &Cvars) -> (Input, Input) {
+        let mut down = Input::new();
+        if !cvars.cl_mouse_aim {
+            return (down, down);
+        }
+
+        let player_handle = self.player_handles()[0];
+        if let Some(vehicle_handle) = server.gs.players[player_handle].vehicle {
+            let vehicle = &server.gs.vehicles[vehicle_handle];
+            let (x, y) = mouse_position();
+            let scr_pos = Vec2f::new(x as f64, y as f64);
+            let cursor = scr_pos / self.cameras[0].zoom - self.camera_offset;
+            down.turret_angle = Some((cursor - vehicle.pos).to_angle());
+        }
+
+        let mut pressed = down;
+        down.fire = is_mouse_button_down(MouseButton::Left);
+        // Check both to avoid skipping clicks shorter than a frame.
+        pressed.fire = down.fire || is_mouse_button_pressed(MouseButton::Left);
+        (pressed, down)
     }
 
-    // The rest are shared actions defined on is player 1 only
+    /// Every local player cycles their vote with their weapon switch binds (X and Y on gamepads).
+    ///
+    /// Number keys also vote for player 1, numpad for player 2.
+    fn process_vote(&mut self, server: &mut Server, inputs: &[(Index, Input, Input)]) {
+        for (i, &(handle, pressed, _)) in inputs.iter().enumerate() {
+            // Only step once per press, gamepads report held buttons as pressed every frame.
+            let prev = self.vote_input_prev.get(i).copied().unwrap_or_default();
+            if pressed.next_weapon && !prev.next_weapon {
+                server.step_vote(handle, true);
+            }
+            if pressed.prev_weapon && !prev.prev_weapon {
+                server.step_vote(handle, false);
+            }
+        }
 
-    if was_input_pressed(&[KeyCode::Enter, KeyCode::T]) {
-        input.chat = true;
+        let player1_handle = inputs[0].0;
+        let player2_handle = inputs.get(1).map(|&(handle, _, _)| handle);
+        let keys1 = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        let keys2 = [
+            KeyCode::Kp1,
+            KeyCode::Kp2,
+            KeyCode::Kp3,
+            KeyCode::Kp4,
+            KeyCode::Kp5,
+            KeyCode::Kp6,
+            KeyCode::Kp7,
+            KeyCode::Kp8,
+            KeyCode::Kp9,
+        ];
+        for option in 0..keys1.len() {
+            if is_key_pressed(keys1[option]) {
+                server.vote(player1_handle, option);
+            }
+            if let Some(player2_handle) = player2_handle {
+                if is_key_pressed(keys2[option]) {
+                    server.vote(player2_handle, option);
+                }
+            }
+        }
     }
-    if was_input_pressed(&[KeyCode::Pause, KeyCode::P]) {
-        input.pause = true;
+}
+
+/// Macroquad doesn't say when exactly keys were pressed and released,
+/// assume pressed right after the previous frame and released halfway to this one.
+fn send_input(
+    server: &mut Server,
+    cvars: &Cvars,
+    player_handle: Index,
+    real_time: f64,
+    pressed: Input,
+    down: Input,
+) {
+    let real_time_prev = server.real_time;
+    server.input(cvars, player_handle, real_time_prev, pressed);
+    if down != pressed {
+        let released_time = (real_time_prev + real_time) / 2.0;
+        server.input(cvars, player_handle, released_time, down);
     }
+}
 
-    input
+/// How the screen is split between local players.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub columns: usize,
+    pub rows: usize,
+    pub viewport_size: Vec2f,
 }
 
-fn get_input2() -> Input {
-    let mut input = Input::new();
-    if was_input_pressed(&[KeyCode::Left]) {
-        input.left = true;
-    }
-    if was_input_pressed(&[KeyCode::Right]) {
-        input.right = true;
-    }
-    if was_input_pressed(&[KeyCode::Up]) {
-        input.up = true;
-    }
-    if was_input_pressed(&[KeyCode::Down]) {
-        input.down = true;
-    }
-    if was_input_pressed(&[KeyCode::Comma]) {
-        input.turret_left = true;
-    }
-    if was_input_pressed(&[KeyCode::Period]) {
-        input.turret_right = true;
-    }
-    if was_input_pressed(&[KeyCode::L]) {
-        input.prev_weapon = true;
-    }
-    if was_input_pressed(&[
-        KeyCode::Slash, // US layout
-        KeyCode::Minus, // Same key, CZ layout
-        KeyCode::Kp0,
-    ]) {
-        input.next_weapon = true;
-    }
-    if was_input_pressed(&[KeyCode::RightShift]) {
-        input.fire = true;
-    }
-    if was_input_pressed(&[KeyCode::M]) {
-        input.mine = true;
+impl Layout {
+    fn new(columns: usize, rows: usize, gap: f64, screen_size: Vec2f) -> Self {
+        let gaps = Vec2f::new((columns - 1) as f64, (rows - 1) as f64) * gap;
+        let cells = Vec2f::new(columns as f64, rows as f64);
+        let viewport_size = ((screen_size - gaps) / cells).floor();
+        Self {
+            columns,
+            rows,
+            viewport_size,
+        }
     }
-    if was_input_pressed(&[KeyCode::J]) {
-        input.self_destruct = true;
+
+    /// Top left corner of the i-th viewport, filled row by row.
+    pub fn viewport_pos(&self, cvars: &Cvars, i: usize) -> Vec2f {
+        let cell = Vec2f::new((i % self.columns) as f64, (i / self.columns) as f64);
+        cell * (self.viewport_size + cvars.r_splitscreen_gap)
     }
-    if was_input_pressed(&[KeyCode::K]) {
-        input.horn = true;
+}
+
+/// Choose the layout for the given number of players according to `r_splitscreen_layout`.
+pub fn splitscreen_layout(cvars: &Cvars, players: usize, screen_size: Vec2f) -> Layout {
+    let gap = cvars.r_splitscreen_gap;
+    let grid_columns = (players as f64).sqrt().ceil() as usize;
+    let grid = Layout::new(
+        grid_columns,
+        (players + grid_columns - 1) / grid_columns,
+        gap,
+        screen_size,
+    );
+    let horizontal = Layout::new(players, 1, gap, screen_size);
+    let vertical = Layout::new(1, players, gap, screen_size);
+    match cvars.r_splitscreen_layout {
+        SplitscreenLayout::Horizontal => horizontal,
+        SplitscreenLayout::Vertical => vertical,
+        SplitscreenLayout::Grid => grid,
+        SplitscreenLayout::Auto => {
+            // Whatever gives the biggest view in the more cramped direction
+            // so each player can see far enough around them.
+            let min_side = |layout: &Layout| layout.viewport_size.x.min(layout.viewport_size.y);
+            [horizontal, grid, vertical]
+                .into_iter()
+                .max_by(|a, b| min_side(a).partial_cmp(&min_side(b)).unwrap())
+                .unwrap()
+        }
     }
+}
+
+fn new_render_target(size: Vec2f) -> RenderTarget {
+    // A minimized window can have zero size.
+    render_target(size.x.max(1.0) as u32, size.y.max(1.0) as u32)
+}
+
+/// Gamepad cvars count from 1 so 0 can mean none.
+fn gamepad_index(cvar: usize) -> Option<usize> {
+    cvar.checked_sub(1)
+}
+
+fn was_input_pressed(key_code: KeyCode) -> bool {
+    // Check both to avoid skipping input if it's pressed and released within one frame.
+    is_key_pressed(key_code) || is_key_down(key_code)
+}
 
-    // No binds for shared actions like chat, pause, console and esc.
-    // They're defined on player 1.
+fn tile_paths(surfaces: &[Surface]) -> Vec<String> {
+    surfaces
+        .iter()
+        .map(|surface| surface.image.clone())
+        .collect()
+}
 
-    input
+/// Load the image of each surface in texture list order.
+///
+/// A missing image is reported and replaced by a magenta placeholder
+/// so a broken custom tileset doesn't prevent playing the map.
+async fn load_tiles(surfaces: &[Surface]) -> Vec<Texture2D> {
+    let textures =
+        future::join_all(surfaces.iter().map(|surface| load_texture(&surface.image))).await;
+    surfaces
+        .iter()
+        .zip(textures)
+        .map(|(surface, res)| {
+            res.unwrap_or_else(|err| {
+                dbg_logf!(
+                    "Missing image for surface {}: {}: {}",
+                    surface.name,
+                    surface.image,
+                    err
+                );
+                // Tiles are drawn at the texture's size so the placeholder has to be full size.
+                let size = TILE_SIZE as u16;
+                let pixels = [255, 0, 255, 255].repeat(size as usize * size as usize);
+                Texture2D::from_rgba8(size, size, &pixels)
+            })
+        })
+        .collect()
 }
 
-fn was_input_pressed(key_codes: &[KeyCode]) -> bool {
-    for &key_code in key_codes {
-        // Check both to avoid skipping input if it's pressed and released within one frame.
-        if is_key_pressed(key_code) || is_key_down(key_code) {
-            return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splitscreen_layout() {
+        let mut cvars = Cvars {
+            r_splitscreen_gap: 0.0,
+            ..Cvars::default()
+        };
+        let wide = Vec2f::new(1920.0, 1080.0);
+        let tall = Vec2f::new(1080.0, 1920.0);
+        let layout = |cvars: &Cvars, players, screen_size| {
+            let layout = splitscreen_layout(cvars, players, screen_size);
+            (layout.columns, layout.rows)
+        };
+
+        assert_eq!(layout(&cvars, 2, wide), (2, 1));
+        assert_eq!(layout(&cvars, 2, tall), (1, 2));
+        assert_eq!(layout(&cvars, 3, wide), (3, 1));
+        assert_eq!(layout(&cvars, 4, wide), (2, 2));
+        assert_eq!(layout(&cvars, 4, Vec2f::new(4000.0, 800.0)), (4, 1));
+
+        cvars.r_splitscreen_layout = SplitscreenLayout::Grid;
+        assert_eq!(layout(&cvars, 3, wide), (2, 2));
+        cvars.r_splitscreen_layout = SplitscreenLayout::Vertical;
+        assert_eq!(layout(&cvars, 2, wide), (1, 2));
+
+        cvars.r_splitscreen_gap = 10.0;
+        cvars.r_splitscreen_layout = SplitscreenLayout::Grid;
+        let grid = splitscreen_layout(&cvars, 4, wide);
+        assert_eq!(grid.viewport_size, Vec2f::new(955.0, 535.0));
+        assert_eq!(grid.viewport_pos(&cvars, 0), Vec2f::new(0.0, 0.0));
+        assert_eq!(grid.viewport_pos(&cvars, 3), Vec2f::new(965.0, 545.0));
+        let last = grid.viewport_pos(&cvars, 3) + grid.viewport_size;
+        assert!(last.x <= wide.x && last.y <= wide.y);
+    }
+
+    #[test]
+    fn test_view() {
+        let viewport = Vec2f::new(800.0, 600.0);
+        let big_map = Vec2f::new(2000.0, 2000.0);
+
+        let view = View::new(viewport, big_map, 1.0, Vec2f::new(1000.0, 1000.0));
+        assert_eq!(view.camera_top_left, Vec2f::new(600.0, 700.0));
+        assert_eq!(view.pos, Vec2f::zero());
+        assert_eq!(view.size, viewport);
+
+        // Zoomed out, the view shows more of the map and still stops at its edges.
+        let view = View::new(viewport, big_map, 0.5, Vec2f::new(100.0, 1900.0));
+        assert_eq!(view.camera_top_left, Vec2f::new(0.0, 800.0));
+        assert_eq!(view.size, viewport);
+
+        // Smaller than the view along X - centered horizontally.
+        let narrow_map = Vec2f::new(1000.0, 2000.0);
+        let view = View::new(viewport, narrow_map, 0.5, Vec2f::new(900.0, 1000.0));
+        assert_eq!(view.camera_top_left, Vec2f::new(0.0, 400.0));
+        assert_eq!(view.pos, Vec2f::new(150.0, 0.0));
+        assert_eq!(view.size, Vec2f::new(500.0, 600.0));
+
+        // Smaller along both, zooming in makes it fill the view again.
+        let small_map = Vec2f::new(640.0, 480.0);
+        let view = View::new(viewport, small_map, 1.0, Vec2f::zero());
+        assert_eq!(view.pos, Vec2f::new(80.0, 60.0));
+        let view = View::new(viewport, small_map, 2.0, Vec2f::new(640.0, 0.0));
+        assert_eq!(view.camera_top_left, Vec2f::new(240.0, 0.0));
+        assert_eq!(view.pos, Vec2f::zero());
+    }
+
+    #[test]
+    fn test_camera_zoom() {
+        let mut cvars = Cvars::default();
+        let tiles = |c: f64, r: f64| Vec2f::new(c, r) * TILE_SIZE;
+        assert_eq!(wanted_zoom(&cvars, tiles(50.0, 60.0)), 1.0);
+        assert_eq!(wanted_zoom(&cvars, tiles(50.0, 125.0)), 0.8);
+        assert_eq!(wanted_zoom(&cvars, tiles(1000.0, 50.0)), 0.5);
+        cvars.cl_zoom = 2.0;
+        assert_eq!(wanted_zoom(&cvars, tiles(50.0, 125.0)), 1.6);
+        cvars.cl_zoom_auto = false;
+        assert_eq!(wanted_zoom(&cvars, tiles(1000.0, 50.0)), 2.0);
+
+        // Smoothing doesn't overshoot and doesn't depend on framerate.
+        let mut camera = CameraState::new(1.0);
+        camera.update(&cvars, 2.0, Vec2f::new(100.0, 0.0), 0.1);
+        assert!(camera.zoom > 1.0 && camera.zoom < 2.0);
+        assert!(camera.look_ahead.x > 0.0 && camera.look_ahead.x < 100.0);
+        let mut camera2 = CameraState::new(1.0);
+        for _ in 0..10 {
+            camera2.update(&cvars, 2.0, Vec2f::new(100.0, 0.0), 0.01);
         }
+        assert!((camera.zoom - camera2.zoom).abs() < 1e-9);
+
+        cvars.cl_zoom_speed = 0.0;
+        camera.update(&cvars, 3.0, Vec2f::zero(), 0.01);
+        assert_eq!(camera.zoom, 3.0);
     }
-    false
 }
@@ -3,17 +3,20 @@
 use std::{cmp::Reverse, str};
 
 use macroquad::prelude::*;
+use strum::IntoEnumIterator;
 use thunderdome::Index;
-use vek::Clamp;
+use vek::{Clamp, Wrap};
 
 use crate::{
+    binds::{key_name, Action, Binds},
     cvars::Cvars,
     debugging::{DEBUG_CROSSES, DEBUG_LINES, DEBUG_TEXTS, DEBUG_TEXTS_WORLD},
-    entities::{Ammo, Weapon},
-    game_state::Explosion,
-    map::{F64Ext, Kind, Vec2f, VecExt, TILE_SIZE},
-    mq::{ClientMode, MacroquadClient},
-    server::Server,
+    editor::{Editor, EDITOR_HELP},
+    entities::{Ammo, Projectile, Vehicle, Weapon},
+    game_state::{Explosion, GameState},
+    map::{F64Ext, Kind, MapMeta, Tile, Vec2f, VecExt, TILE_SIZE},
+    mq::{CameraState, ClientMode, MacroquadClient, View},
+    server::{MatchState, Server, Vote},
 };
 
 // LATER clean up at least some of the casts here
@@ -22,40 +25,63 @@ pub fn render(client: &mut MacroquadClient, server: &Server, cvars: &Cvars) {
     client.render_fps.tick(cvars.d_fps_period, server.real_time);
     let start = get_time();
 
+    client.update_cameras(server, cvars, get_frame_time() as f64);
+
     match client.client_mode {
         ClientMode::Singleplayer { player_handle } => {
-            render_viewport(client, server, cvars, player_handle)
+            let camera = client.cameras[0];
+            client.camera_offset =
+                render_viewport(client, server, cvars, player_handle, camera, None);
+            set_default_camera();
         }
-        ClientMode::Splitscreen {
-            render_targets,
-            player_handles,
-        } => {
-            let rect = Rect::new(
-                0.0,
-                0.0,
-                client.viewport_size.x as f32,
-                client.viewport_size.y as f32,
-            );
-            let mut camera = Camera2D::from_display_rect(rect);
-            camera.zoom.y = -camera.zoom.y; // Macroquad bug https://github.com/not-fl3/macroquad/issues/171
-
-            camera.render_target = Some(render_targets.0);
-            set_camera(&camera);
-            clear_background(BLANK);
-            render_viewport(client, server, cvars, player_handles.0);
-
-            camera.render_target = Some(render_targets.1);
-            set_camera(&camera);
-            clear_background(BLANK);
-            render_viewport(client, server, cvars, player_handles.1);
+        ClientMode::Splitscreen { ref viewports } => {
+            let mut camera_offsets = Vec::new();
+            for (viewport, &camera) in viewports.iter().zip(&client.cameras) {
+                let render_target = Some(viewport.render_target);
+                set_camera(&viewport_camera(client.viewport_size, 1.0, render_target));
+                clear_background(BLANK);
+                let offset = render_viewport(
+                    client,
+                    server,
+                    cvars,
+                    viewport.player_handle,
+                    camera,
+                    render_target,
+                );
+                camera_offsets.push(offset);
+            }
+            // Mouse aiming is for player 1, their viewport is in the top left corner.
+            client.camera_offset = camera_offsets[0];
 
             set_default_camera();
-            draw_texture(render_targets.0.texture, 0.0, 0.0, WHITE);
-            let offset_x = (client.viewport_size.x + cvars.r_splitscreen_gap) as f32;
-            draw_texture(render_targets.1.texture, offset_x, 0.0, WHITE);
+            for viewport in viewports {
+                let pos = viewport.pos;
+                draw_texture(
+                    viewport.render_target.texture,
+                    pos.x as f32,
+                    pos.y as f32,
+                    WHITE,
+                );
+            }
         }
     }
 
+    if let MatchState::Voting(vote) = &server.match_state {
+        render_vote(client, server, cvars, vote);
+    }
+
+    if cvars.hud_binds {
+        render_binds(client, cvars);
+    }
+
+    if cvars.cl_mouse_aim {
+        let (x, y) = mouse_position();
+        let r = cvars.hud_crosshair_radius as f32;
+        draw_circle_lines(x, y, r, 1.0, RED);
+        draw_line(x - r * 1.5, y, x + r * 1.5, y, 1.0, RED);
+        draw_line(x, y - r * 1.5, x, y + r * 1.5, 1.0, RED);
+    }
+
     render_shared(client, server, cvars);
 
     let end = get_time();
@@ -64,51 +90,62 @@ pub fn render(client: &mut MacroquadClient, server: &Server, cvars: &Cvars) {
         .add(cvars.d_timing_samples, end - start);
 }
 
+/// Render one local player's view of the game.
+///
+/// The world is drawn zoomed, the HUD isn't. Returns the camera offset in zoomed coords.
 fn render_viewport(
     client: &MacroquadClient,
     server: &Server,
     cvars: &Cvars,
     local_player_handle: Index,
-) {
+    camera: CameraState,
+    render_target: Option<RenderTarget>,
+) -> Vec2f {
     // This is one long function. A lot of people will tell you that's bad™
     // because they've heard it from other people who think long functions are bad™.
     // Most of those people haven't written a game bigger than snake. Carmack says it's ok so it's ok:
     // http://number-none.com/blow/blog/programming/2014/09/26/carmack-on-inlined-code.html
 
+    let lerp = Lerp(server.interpolation(cvars));
+
     let player = &server.gs.players[local_player_handle];
-    let player_vehicle = &server.gs.vehicles[player.vehicle.unwrap()];
+    let player_vehicle_handle = player.vehicle.unwrap();
+    let player_vehicle = &server.gs.vehicles[player_vehicle_handle];
+    let (player_vehicle_pos, _, _) = lerp.vehicle(player_vehicle_handle, player_vehicle);
     let player_entity_pos = if let Some(gm_handle) = player.guided_missile {
-        server.gs.projectiles[gm_handle].pos
+        lerp.projectile(gm_handle, &server.gs.projectiles[gm_handle])
     } else {
-        player_vehicle.pos
+        player_vehicle_pos
     };
 
-    // Don't put the camera so close to the edge that it would render area outside the map.
-    // Also properly handle maps smaller than view size. Note they can be smaller along X, Y or both.
-    // Example maps for testing: Joust (2), extra/OK Corral (2)
-    let map_size = server.map.maxs();
-    let view_size = Vec2f::new(
-        client.viewport_size.x.min(map_size.x),
-        client.viewport_size.y.min(map_size.y),
+    let zoom = camera.zoom;
+    let view = View::new(
+        client.viewport_size,
+        server.map.maxs(),
+        zoom,
+        player_entity_pos + camera.look_ahead,
     );
+    // Screen coords, used by the HUD.
+    let view_size = view.size;
+    let view_pos = view.pos;
     let empty_space_size = client.viewport_size - view_size;
-    let view_pos = empty_space_size / 2.0;
-
-    // Camera center in world coords.
-    let camera_center_min = view_size / 2.0;
-    let camera_center_max = map_size - camera_center_min;
-    let camera_center = player_entity_pos.clamped(camera_center_min, camera_center_max);
+    // Zoomed coords, used by everything in the world.
+    // They become screen coords when multiplied by `zoom` which is done by the camera.
+    let viewport_size = client.viewport_size / zoom;
+    let world_view_size = view_size / zoom;
+    let world_view_pos = view_pos / zoom;
+    set_camera(&viewport_camera(client.viewport_size, zoom, render_target));
 
     // Position of the camera's top left corner in world coords.
-    let camera_top_left = camera_center - camera_center_min;
-    // Add this to world coords to get screen coords.
+    let camera_top_left = view.camera_top_left;
+    // Add this to world coords to get (zoomed) screen coords.
     // Forgetting to do this is a recurring source of bugs.
     // I've considered making a special type for screen coords (e.g. struct Vec2screen(Vec2f);)
     // so you couldn't accidentally pass world coords to drawing fns but it turned out to be more work than expected:
     // - The newtype had to manually impl all the needed operations of the underlying Vec2 type because ops don't autoderef.
     // - What would be the result of ops that take one world coord and one screen coord? Lots of cases to think about.
     // - Which type are sizes? Another type? E.g. `center = corner + size/2` makes sense in both screen and world coords.
-    let camera_offset = -camera_top_left + view_pos;
+    let camera_offset = -camera_top_left + world_view_pos;
 
     let top_left_tp = server.map.tile_pos(camera_top_left);
     let top_left_index = top_left_tp.index;
@@ -121,15 +158,15 @@ fn render_viewport(
     // Draw non-walls
     let mut r = top_left_index.y;
     let mut y = -bg_offset.y;
-    while y < view_size.y {
+    while y < world_view_size.y {
         let mut c = top_left_index.x;
         let mut x = -bg_offset.x;
-        while x < view_size.x {
+        while x < world_view_size.x {
             let tile = server.map.col_row(c, r);
 
             if server.map.surface_of(tile).kind != Kind::Wall {
                 let img = client.imgs_tiles[tile.surface_index];
-                render_tile(img, view_pos.x + x, view_pos.y + y, tile.angle);
+                render_tile(img, world_view_pos.x + x, world_view_pos.y + y, tile.angle);
             }
 
             c += 1;
@@ -148,8 +185,8 @@ fn render_viewport(
             .filter(move |(_, proj)| proj.weapon == weapon)
     };
 
-    let outside_view_top_left = view_pos - TILE_SIZE;
-    let outside_view_bottom_right = view_pos + view_size + TILE_SIZE;
+    let outside_view_top_left = world_view_pos - TILE_SIZE;
+    let outside_view_bottom_right = world_view_pos + world_view_size + TILE_SIZE;
     // Is the object certainly outside camera view?
     // Only works on objects smaller that tile size, which is most.
     // Exceptions are lines and text.
@@ -161,8 +198,8 @@ fn render_viewport(
     };
 
     // Draw MGs
-    for (_, mg) in weapon_projectiles(Weapon::Mg) {
-        let scr_pos = mg.pos + camera_offset;
+    for (handle, mg) in weapon_projectiles(Weapon::Mg) {
+        let scr_pos = lerp.projectile(handle, mg) + camera_offset;
         if cull(scr_pos) {
             continue;
         }
@@ -189,22 +226,22 @@ fn render_viewport(
     }
 
     // Draw rockets, homing and guided missiles
-    for (_, proj) in weapon_projectiles(Weapon::Rockets) {
-        let scr_pos = proj.pos + camera_offset;
+    for (handle, proj) in weapon_projectiles(Weapon::Rockets) {
+        let scr_pos = lerp.projectile(handle, proj) + camera_offset;
         if cull(scr_pos) {
             continue;
         }
         render_img_center(client.img_rocket, scr_pos, proj.vel.to_angle());
     }
-    for (_, proj) in weapon_projectiles(Weapon::Hm) {
-        let scr_pos = proj.pos + camera_offset;
+    for (handle, proj) in weapon_projectiles(Weapon::Hm) {
+        let scr_pos = lerp.projectile(handle, proj) + camera_offset;
         if cull(scr_pos) {
             continue;
         }
         render_img_center(client.img_hm, scr_pos, proj.vel.to_angle());
     }
-    for (_, proj) in weapon_projectiles(Weapon::Gm) {
-        let scr_pos = proj.pos + camera_offset;
+    for (handle, proj) in weapon_projectiles(Weapon::Gm) {
+        let scr_pos = lerp.projectile(handle, proj) + camera_offset;
         if cull(scr_pos) {
             continue;
         }
@@ -214,8 +251,8 @@ fn render_viewport(
     // Draw BFGs
     // client.context.set_fill_style(&"lime".into());
     // client.context.set_stroke_style(&"lime".into());
-    for (_, bfg) in weapon_projectiles(Weapon::Bfg) {
-        let scr_pos = bfg.pos + camera_offset;
+    for (handle, bfg) in weapon_projectiles(Weapon::Bfg) {
+        let scr_pos = lerp.projectile(handle, bfg) + camera_offset;
         if cull(scr_pos) {
             continue;
         }
@@ -233,8 +270,9 @@ fn render_viewport(
     }
 
     // Draw chassis
-    for (_, vehicle) in server.gs.vehicles.iter() {
-        let scr_pos = vehicle.pos + camera_offset;
+    for (handle, vehicle) in server.gs.vehicles.iter() {
+        let (pos, angle, _) = lerp.vehicle(handle, vehicle);
+        let scr_pos = pos + camera_offset;
         if cull(scr_pos) {
             continue;
         }
@@ -243,7 +281,7 @@ fn render_viewport(
         } else {
             client.imgs_vehicles[vehicle.veh_type as usize * 2]
         };
-        render_img_center(img, scr_pos, vehicle.angle);
+        render_img_center(img, scr_pos, angle);
         // LATER draw hitboxes
         // if cvars.d_draw && cvars.d_draw_hitboxes {
         //     client.context.set_stroke_style(&"yellow".into());
@@ -261,27 +299,23 @@ fn render_viewport(
     // TODO Draw cow
 
     // Draw turrets
-    for (_, vehicle) in server.gs.vehicles.iter() {
+    for (handle, vehicle) in server.gs.vehicles.iter() {
         if vehicle.destroyed() {
             continue;
         }
 
-        let scr_pos = vehicle.pos + camera_offset;
+        let (pos, angle, turret_angle) = lerp.vehicle(handle, vehicle);
+        let scr_pos = pos + camera_offset;
         if cull(scr_pos) {
             continue;
         }
 
         let img = client.imgs_vehicles[vehicle.veh_type as usize * 2 + 1];
         let offset_chassis =
-            vehicle.angle.to_mat2f() * cvars.g_vehicle_turret_offset_chassis(vehicle.veh_type);
+            angle.to_mat2f() * cvars.g_vehicle(vehicle.veh_type).turret_offset_chassis();
         let turret_scr_pos = scr_pos + offset_chassis;
-        let offset_turret = cvars.g_vehicle_turret_offset_turret(vehicle.veh_type);
-        render_img_offset(
-            img,
-            turret_scr_pos,
-            vehicle.angle + vehicle.turret_angle_current,
-            offset_turret,
-        );
+        let offset_turret = cvars.g_vehicle(vehicle.veh_type).turret_offset_turret();
+        render_img_offset(img, turret_scr_pos, angle + turret_angle, offset_turret);
     }
 
     // Draw explosions
@@ -336,15 +370,15 @@ fn render_viewport(
     // They are above explosions and turrets, just like in RecWar.
     let mut r = top_left_index.y;
     let mut y = -bg_offset.y;
-    while y < view_size.y {
+    while y < world_view_size.y {
         let mut c = top_left_index.x;
         let mut x = -bg_offset.x;
-        while x < view_size.x {
+        while x < world_view_size.x {
             let tile = server.map.col_row(c, r);
 
             if server.map.surface_of(tile).kind == Kind::Wall {
                 let img = client.imgs_tiles[tile.surface_index];
-                render_tile(img, view_pos.x + x, view_pos.y + y, tile.angle);
+                render_tile(img, world_view_pos.x + x, world_view_pos.y + y, tile.angle);
             }
 
             c += 1;
@@ -387,8 +421,9 @@ fn render_viewport(
 
     // Names
     if cvars.hud_names {
-        for (_, vehicle) in server.gs.vehicles.iter() {
-            let scr_pos = vehicle.pos + camera_offset;
+        for (handle, vehicle) in server.gs.vehicles.iter() {
+            let (pos, _, _) = lerp.vehicle(handle, vehicle);
+            let scr_pos = pos + camera_offset;
             if cull(scr_pos) {
                 // LATER, restrict name length
                 continue;
@@ -417,7 +452,7 @@ fn render_viewport(
 
     // Homing missile indicator
     // TODO dashed lines (maybe use image)
-    let player_veh_scr_pos = player_vehicle.pos + camera_offset;
+    let player_veh_scr_pos = player_vehicle_pos + camera_offset;
     draw_circle_lines(
         player_veh_scr_pos.x as f32,
         player_veh_scr_pos.y as f32,
@@ -432,7 +467,7 @@ fn render_viewport(
     // Spawn location indicator
     let alive_time = server.gs.game_time - player_vehicle.spawn_time;
     if alive_time < cvars.cl_spawn_indicator_duration {
-        let vehicle_scr_pos = player_vehicle.pos + camera_offset;
+        let vehicle_scr_pos = player_vehicle_pos + camera_offset;
 
         // Radius here is distance from the square's center to its side.
         let max_radius = cvars.cl_spawn_indicator_square_side_begin / 2.0;
@@ -453,7 +488,7 @@ fn render_viewport(
         draw_line(
             vehicle_scr_pos.x as f32 + min_radius,
             vehicle_scr_pos.y as f32,
-            client.viewport_size.x as f32,
+            viewport_size.x as f32,
             vehicle_scr_pos.y as f32,
             cvars.cl_spawn_indicator_thickness,
             GREEN,
@@ -470,7 +505,7 @@ fn render_viewport(
             vehicle_scr_pos.x as f32,
             vehicle_scr_pos.y as f32 + min_radius,
             vehicle_scr_pos.x as f32,
-            client.viewport_size.y as f32,
+            viewport_size.y as f32,
             cvars.cl_spawn_indicator_thickness,
             GREEN,
         );
@@ -544,6 +579,7 @@ fn render_viewport(
     });
 
     // Draw screen-space HUD elements:
+    set_camera(&viewport_camera(client.viewport_size, 1.0, render_target));
 
     let mut player_points: Vec<_> = server
         .gs
@@ -660,7 +696,7 @@ fn render_viewport(
     let ammo = player_vehicle.ammos[player.cur_weapon as usize];
     let ammo_fraction = match ammo {
         Ammo::Loaded(_ready_time, count) => {
-            let max = cvars.g_weapon_reload_ammo(player.cur_weapon);
+            let max = cvars.g_weapon(player.cur_weapon).reload_ammo;
             count as f64 / max as f64
         }
         Ammo::Reloading(start, end) => {
@@ -832,12 +868,15 @@ fn render_viewport(
         let texts = texts.borrow();
         if cvars.d_draw && cvars.d_draw_world_text {
             for text in texts.iter() {
-                let scr_pos = text.pos + camera_offset;
-                if cull(scr_pos) {
+                let zoomed_pos = text.pos + camera_offset;
+                if cull(zoomed_pos) {
                     // LATER Technically the text can be so long
                     // that it's culled overzealously but meh, perf is more important.
                     continue;
                 }
+                // Unlike the rest of the world, the text is drawn after switching to the HUD camera
+                // so it's readable at any zoom.
+                let scr_pos = zoomed_pos * zoom;
 
                 render_text_with_shadow(
                     cvars,
@@ -853,6 +892,172 @@ fn render_viewport(
             }
         }
     });
+
+    camera_offset
+}
+
+/// Camera drawing into a viewport of the given size where each unit is `zoom` screen pixels.
+fn viewport_camera(
+    viewport_size: Vec2f,
+    zoom: f64,
+    render_target: Option<RenderTarget>,
+) -> Camera2D {
+    let size = viewport_size / zoom;
+    let rect = Rect::new(0.0, 0.0, size.x as f32, size.y as f32);
+    let mut camera = Camera2D::from_display_rect(rect);
+    if render_target.is_some() {
+        camera.zoom.y = -camera.zoom.y; // Macroquad bug https://github.com/not-fl3/macroquad/issues/171
+    }
+    camera.render_target = render_target;
+    camera
+}
+
+fn render_vote(client: &MacroquadClient, server: &Server, cvars: &Cvars, vote: &Vote) {
+    let screen_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
+    let x = screen_size.x as f32 / 2.0 - 200.0;
+    let mut y = screen_size.y as f32 / 2.0 - 100.0;
+    let time_left = (vote.end_time - server.real_time).max(0.0);
+    let title = format!("Match over - vote for the next map ({:.0} s)", time_left);
+    render_text_with_shadow(cvars, &title, x, y, 32.0, WHITE, 1.0, 1.0, 1.0);
+    y += 40.0;
+
+    let counts = vote.counts();
+    for (i, option) in vote.options.iter().enumerate() {
+        let name = MapMeta::from_path(option).name;
+        let mut text = format!("{}) {} - {} votes", i + 1, name, counts[i]);
+        // Show local players where their vote is since they can cycle through the options.
+        let local: Vec<_> = client
+            .player_handles()
+            .iter()
+            .enumerate()
+            .filter(|(_, handle)| vote.votes.get(handle) == Some(&i))
+            .map(|(j, _)| format!("P{}", j + 1))
+            .collect();
+        if !local.is_empty() {
+            text += &format!(" <- {}", local.join(", "));
+        }
+        render_text_with_shadow(cvars, &text, x, y, 24.0, YELLOW, 1.0, 1.0, 1.0);
+        y += 30.0;
+    }
+}
+
+/// One column per player with the keys of each action.
+fn render_binds(client: &MacroquadClient, cvars: &Cvars) {
+    let fs = cvars.hud_binds_font_size;
+    let line_height = fs as f32 * 1.25;
+    let top = 60.0;
+
+    // In singleplayer, all the tables control the same player so list them below each other,
+    // in splitscreen, each player gets a column.
+    let columns = client.player_handles().len();
+    let column_width = screen_width() / columns as f32;
+    let tables = if columns == 1 {
+        cvars.cl_binds.len()
+    } else {
+        columns
+    };
+    let mut ys = vec![top; columns];
+    for (i, binds) in cvars.cl_binds.iter().enumerate().take(tables) {
+        // Players 3 and 4 have no default binds.
+        if columns == 1 && *binds == Binds::new() {
+            continue;
+        }
+        let column = i % columns;
+        let x = 20.0 + column_width * column as f32;
+        let y = &mut ys[column];
+
+        let cmd = if i == 0 {
+            "bind".to_owned()
+        } else {
+            format!("bind{}", i + 1)
+        };
+        let title = format!("Player {} ({cmd} <key>=<action> in console)", i + 1);
+        render_text_with_shadow(cvars, &title, x, *y, fs, YELLOW, 1.0, 1.0, 1.0);
+        *y += line_height;
+        for action in Action::iter() {
+            let keys: Vec<_> = binds.keys(action).into_iter().map(key_name).collect();
+            if keys.is_empty() {
+                continue;
+            }
+            let text = format!("{action}: {}", keys.join(" "));
+            render_text_with_shadow(cvars, &text, x, *y, fs, WHITE, 1.0, 1.0, 1.0);
+            *y += line_height;
+        }
+        *y += line_height;
+    }
+
+    // Some keys are reported differently depending on layout, show what MQ thinks was pressed.
+    let last_key = client.last_key.map(key_name).unwrap_or_default();
+    let text = format!("Last pressed key: {last_key}");
+    let y = screen_height() - 20.0;
+    render_text_with_shadow(cvars, &text, 20.0, y, fs, YELLOW, 1.0, 1.0, 1.0);
+}
+
+pub fn render_editor(client: &mut MacroquadClient, editor: &Editor, cvars: &Cvars) {
+    let start = get_time();
+
+    let map = &editor.map;
+    let screen_size = Vec2f::new(screen_width() as f64, screen_height() as f64);
+    let camera_offset = -editor.camera_top_left.floor();
+
+    // Draw all tiles including walls - there are no entities to put between them.
+    let first = map.tile_pos(editor.camera_top_left).index;
+    let last = map.tile_pos(editor.camera_top_left + screen_size).index;
+    for r in first.y..=last.y {
+        for c in first.x..=last.x {
+            let tile = map.col_row(c, r);
+            let scr_pos = Vec2f::new(c as f64, r as f64) * TILE_SIZE + camera_offset;
+            let img = client.imgs_tiles[tile.surface_index];
+            render_tile(img, scr_pos.x, scr_pos.y, tile.angle);
+        }
+    }
+
+    // Preview what would be painted under the cursor
+    if let Some(index) = editor.cursor {
+        let scr_pos = index.as_() * TILE_SIZE + camera_offset;
+        draw_texture_ex(
+            client.imgs_tiles[editor.surface_index],
+            scr_pos.x as f32,
+            scr_pos.y as f32,
+            Color::new(1.0, 1.0, 1.0, 0.6),
+            DrawTextureParams {
+                rotation: Tile::new(editor.surface_index, editor.rotation).angle as f32,
+                ..Default::default()
+            },
+        );
+        draw_rectangle_lines(
+            scr_pos.x as f32,
+            scr_pos.y as f32,
+            TILE_SIZE as f32,
+            TILE_SIZE as f32,
+            2.0,
+            YELLOW,
+        );
+    }
+
+    let surface = &map.surfaces()[editor.surface_index];
+    let mut lines = vec![
+        EDITOR_HELP.to_owned(),
+        format!(
+            "{} - surface {} ({:?}), rotation {}",
+            editor.map_path, surface.name, surface.kind, editor.rotation
+        ),
+    ];
+    if let Some(index) = editor.cursor {
+        let surface = map.surface_at_index(index);
+        lines.push(format!("({}, {}) {}", index.x, index.y, surface.name));
+    }
+    lines.extend(editor.messages.iter().cloned());
+    let mut y = screen_size.y as f32 - 10.0 - 20.0 * (lines.len() - 1) as f32;
+    for line in &lines {
+        render_text_with_shadow(cvars, line, 10.0, y, 20.0, WHITE, 1.0, 1.0, 1.0);
+        y += 20.0;
+    }
+
+    let end = get_time();
+    client
+        .render_cmds_durations
+        .add(cvars.d_timing_samples, end - start);
 }
 
 fn render_shared(client: &MacroquadClient, server: &Server, cvars: &Cvars) {
@@ -956,6 +1161,26 @@ fn render_shared(client: &MacroquadClient, server: &Server, cvars: &Cvars) {
                 0.5,
             );
         }
+        if let Some((avg, max)) = server.dropped_durations.get_stats() {
+            // Game time, not how long it took.
+            let text = format!(
+                "dropped avg: {:.1}, max: {:.1}, total: {:.0}",
+                avg * 1000.0,
+                max * 1000.0,
+                server.dropped_time * 1000.0
+            );
+            render_text_with_shadow(
+                cvars,
+                &text,
+                screen_size.x as f32 - 280.0,
+                screen_size.y as f32 - 30.0,
+                16.0,
+                RED,
+                1.0,
+                1.0,
+                0.5,
+            );
+        }
     }
 
     // Draw last key
@@ -988,6 +1213,53 @@ fn render_shared(client: &MacroquadClient, server: &Server, cvars: &Cvars) {
     });
 }
 
+/// Entity transforms to render, interpolated between the last two ticks
+/// with `TickrateMode::FixedWithInterpolation`, see `Server::interpolation`.
+struct Lerp<'a>(Option<(&'a GameState, f64)>);
+
+impl Lerp<'_> {
+    /// Position, angle and turret angle.
+    fn vehicle(&self, handle: Index, vehicle: &Vehicle) -> (Vec2f, f64, f64) {
+        let current = (vehicle.pos, vehicle.angle, vehicle.turret_angle_current);
+        // Respawned vehicles get a new handle, checking spawn time too in case that changes.
+        // Either way there's nothing to interpolate from.
+        let (prev, alpha) = match self.0 {
+            Some((gs_prev, alpha)) if vehicle.spawn_time <= gs_prev.game_time => {
+                match gs_prev.vehicles.get(handle) {
+                    Some(prev) => (prev, alpha),
+                    None => return current,
+                }
+            }
+            _ => return current,
+        };
+        (
+            Vec2f::lerp(prev.pos, vehicle.pos, alpha),
+            lerp_angle(prev.angle, vehicle.angle, alpha),
+            lerp_angle(
+                prev.turret_angle_current,
+                vehicle.turret_angle_current,
+                alpha,
+            ),
+        )
+    }
+
+    fn projectile(&self, handle: Index, projectile: &Projectile) -> Vec2f {
+        // Not found if fired since the previous tick - start from the current position.
+        match self.0 {
+            Some((gs_prev, alpha)) => match gs_prev.projectiles.get(handle) {
+                Some(prev) => Vec2f::lerp(prev.pos, projectile.pos, alpha),
+                None => projectile.pos,
+            },
+            None => projectile.pos,
+        }
+    }
+}
+
+/// Interpolate the shorter way around.
+fn lerp_angle(prev: f64, current: f64, alpha: f64) -> f64 {
+    prev + prev.delta_angle(current) * alpha
+}
+
 /// Place the image's *center* at `scr_pos`,
 /// rotate it clockwise by `angle`.
 ///
@@ -1088,3 +1360,53 @@ fn hud_pos(rect_pos: Vec2f, rect_size: Vec2f, mut x: f64, mut y: f64) -> Vec2 {
     }
     Vec2::new((rect_pos.x + x) as f32, (rect_pos.y + y) as f32)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::{cvars::TickrateMode, map};
+
+    #[test]
+    fn test_lerp_vehicle() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = map::load_tex_list(&tex_list_text).unwrap();
+        let map_text = fs::read_to_string("maps/Atrium.map").unwrap();
+        let map = map::load_map(&map_text, surfaces).unwrap();
+
+        let cvars = Cvars {
+            d_seed: 42,
+            sv_tickrate_mode: TickrateMode::FixedWithInterpolation,
+            sv_tickrate_fixed_fps: 25.0,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, map);
+        server.update(&cvars, 0.5);
+        let (gs_prev, alpha) = server.interpolation(&cvars).unwrap();
+        assert!(alpha > 0.0);
+        let lerp = Lerp(Some((gs_prev, alpha)));
+
+        let (handle, prev) = gs_prev.vehicles.iter().next().unwrap();
+        let mut vehicle = prev.clone();
+        vehicle.pos += Vec2f::new(100.0, 0.0);
+        let current = (vehicle.pos, vehicle.angle, vehicle.turret_angle_current);
+
+        // Existed in the previous tick - interpolated.
+        let (pos, _, _) = lerp.vehicle(handle, &vehicle);
+        assert_eq!(pos, Vec2f::lerp(prev.pos, vehicle.pos, alpha));
+        assert_ne!(pos, vehicle.pos);
+
+        // Respawned since the previous tick.
+        let mut respawned = vehicle.clone();
+        respawned.spawn_time = gs_prev.game_time + 0.01;
+        assert_eq!(lerp.vehicle(handle, &respawned), current);
+
+        // Handle not in the previous tick.
+        let mut gs = gs_prev.clone();
+        let new_handle = gs.vehicles.insert(vehicle.clone());
+        assert!(gs_prev.vehicles.get(new_handle).is_none());
+        assert_eq!(lerp.vehicle(new_handle, &vehicle), current);
+    }
+}
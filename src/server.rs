@@ -1,10 +1,13 @@
 //! The authoritative server in a client-server game architecture - all data affecting gameplay, no networking yet.
 
+use std::{iter, mem};
+
+use fnv::FnvHashMap;
 use rand::{prelude::SmallRng, SeedableRng};
 use thunderdome::Index;
 
 use crate::{
-    cvars::{Cvars, TickrateMode},
+    cvars::{Cvars, TickrateMode, TickrateOverload},
     debugging,
     entities::{Ai, Player},
     game_state::{ArenaExt, GameState, Input},
@@ -40,9 +43,7 @@ const BOT_NAMES: [&str; 20] = [
 pub struct Server {
     pub map: Map,
     pub gs: GameState,
-    /// Game time left over from previous update.
-    pub dt_carry: f64,
-    pub gs_fixed: GameState,
+    tickrate: Tickrate,
     /// Time since game started in seconds. Increases at wall clock speed even when paused.
     ///
     /// This is not meant to be used for anything that affects gameplay - use `gs.game_time` instead.
@@ -54,6 +55,133 @@ pub struct Server {
     pub update_durations: Durations,
     pub gamelogic_fps: Fps,
     pub gamelogic_durations: Durations,
+    /// Game time thrown away because the server couldn't keep up, see `sv_tickrate_max_catch_up`.
+    pub dropped_time: f64,
+    /// Game time dropped in each update.
+    pub dropped_durations: Durations,
+    pub match_state: MatchState,
+}
+
+/// State specific to the current `TickrateMode`.
+///
+/// Recreated from `Server::gs` when the mode changes so it can be switched mid-match.
+#[derive(Debug)]
+enum Tickrate {
+    Synchronized,
+    Fixed {
+        /// Game time left over from the previous update.
+        dt_carry: f64,
+    },
+    FixedOrSmaller {
+        /// State after the last full step, the smaller step is thrown away
+        /// and simulated again from here next update.
+        gs_fixed: Box<GameState>,
+        /// Game time the server didn't have time to simulate, see `TickrateOverload::SlowDown`.
+        dt_carry: f64,
+    },
+    FixedWithInterpolation {
+        /// State before the last tick, rendering interpolates between this and `Server::gs`.
+        gs_prev: Box<GameState>,
+        dt_carry: f64,
+    },
+}
+
+impl Tickrate {
+    fn new(mode: TickrateMode, gs: &GameState) -> Self {
+        match mode {
+            TickrateMode::Synchronized => Tickrate::Synchronized,
+            TickrateMode::Fixed => Tickrate::Fixed { dt_carry: 0.0 },
+            TickrateMode::FixedOrSmaller => Tickrate::FixedOrSmaller {
+                gs_fixed: Box::new(gs.clone()),
+                dt_carry: 0.0,
+            },
+            TickrateMode::FixedWithInterpolation => Tickrate::FixedWithInterpolation {
+                gs_prev: Box::new(gs.clone()),
+                dt_carry: 0.0,
+            },
+        }
+    }
+
+    fn mode(&self) -> TickrateMode {
+        match self {
+            Tickrate::Synchronized => TickrateMode::Synchronized,
+            Tickrate::Fixed { .. } => TickrateMode::Fixed,
+            Tickrate::FixedOrSmaller { .. } => TickrateMode::FixedOrSmaller,
+            Tickrate::FixedWithInterpolation { .. } => TickrateMode::FixedWithInterpolation,
+        }
+    }
+
+    /// Game time which is yet to be simulated.
+    fn dt_carry(&self) -> f64 {
+        match *self {
+            Tickrate::Synchronized => 0.0,
+            Tickrate::Fixed { dt_carry }
+            | Tickrate::FixedOrSmaller { dt_carry, .. }
+            | Tickrate::FixedWithInterpolation { dt_carry, .. } => dt_carry,
+        }
+    }
+
+    /// The game state which will be simulated next update if it's not `Server::gs`.
+    ///
+    /// Changes made outside gamelogic (e.g. input) have to be applied to both.
+    fn gs_fixed_mut(&mut self) -> Option<&mut GameState> {
+        match self {
+            Tickrate::FixedOrSmaller { gs_fixed, .. } => Some(gs_fixed),
+            _ => None,
+        }
+    }
+}
+
+/// Phase of the match - the game is frozen after it ends.
+#[derive(Debug, Clone)]
+pub enum MatchState {
+    Playing,
+    /// The match ended, players are voting for the next map.
+    Voting(Vote),
+    /// The vote is over, the caller should load `next_map` and start a new `Server`.
+    Finished {
+        next_map: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Vote {
+    /// Map paths - the first is the next map in rotation and wins ties.
+    pub options: Vec<String>,
+    /// Index into `options` for each player who voted.
+    pub votes: FnvHashMap<Index, usize>,
+    /// Real time when the vote ends even if not everybody voted.
+    pub end_time: f64,
+}
+
+impl Vote {
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.options.len()];
+        for &option in self.votes.values() {
+            counts[option] += 1;
+        }
+        counts
+    }
+
+    /// Index of the option with the most votes, ties go to the earlier option.
+    pub fn winner(&self) -> usize {
+        let counts = self.counts();
+        let max = counts.iter().copied().max().unwrap_or(0);
+        counts.iter().position(|&count| count == max).unwrap_or(0)
+    }
+
+    /// The option after (or before) the player's current vote, wrapping around.
+    ///
+    /// Players who haven't voted yet start at the first (or last) option.
+    pub fn stepped(&self, player_handle: Index, forward: bool) -> usize {
+        let len = self.options.len();
+        match (self.votes.get(&player_handle), forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(&option), true) => (option + 1) % len,
+            (Some(&option), false) => (option + len - 1) % len,
+        }
+    }
 }
 
 impl Server {
@@ -61,10 +189,13 @@ impl Server {
         let rng = SmallRng::seed_from_u64(cvars.d_seed);
         let mut gs = GameState::new(rng);
 
-        let bots_count = map.spawns().len().min(cvars.bots_max);
+        let mut bots_count = map.spawns_count().min(cvars.bots_max);
+        if let Some(bots) = map.meta().bots {
+            bots_count = bots_count.min(bots);
+        }
         dbg_logf!(
             "Spawns per bot: {}",
-            map.spawns().len() as f64 / bots_count as f64
+            map.spawns_count() as f64 / bots_count as f64
         );
         dbg_logf!(
             "Tiles per bot: {}",
@@ -78,7 +209,8 @@ impl Server {
             };
             let player = Player::new(name);
             let player_handle = gs.players.insert(player);
-            gs.ais.insert(Ai::new(player_handle));
+            let brain = sys_ai::new_brain("random", &mut gs.rng).unwrap();
+            gs.ais.insert(Ai::new(player_handle, brain));
         }
 
         for handle in gs.players.iter_handles() {
@@ -87,9 +219,8 @@ impl Server {
 
         Self {
             map,
-            gs: gs.clone(),
-            dt_carry: 0.0,
-            gs_fixed: gs,
+            tickrate: Tickrate::new(cvars.sv_tickrate_mode, &gs),
+            gs,
             real_time: 0.0,
             real_time_prev: 0.0,
             real_time_delta: 0.0,
@@ -98,27 +229,116 @@ impl Server {
             update_durations: Durations::new(),
             gamelogic_fps: Fps::new(),
             gamelogic_durations: Durations::new(),
+            dropped_time: 0.0,
+            dropped_durations: Durations::new(),
+            match_state: MatchState::Playing,
         }
     }
 
     pub fn connect(&mut self, cvars: &Cvars, name: &str) -> Index {
         let player = Player::new(name.to_owned());
-        let player_handle = self.gs.players.insert(player.clone());
-        let player_handle2 = self.gs_fixed.players.insert(player);
-        assert_eq!(player_handle, player_handle2);
+        let player_handle = self.gs.players.insert(player);
         systems::spawn_vehicle(cvars, &mut self.gs, &self.map, player_handle, true);
+        // Continue from here, otherwise the player would only exist in the smaller step.
+        self.tickrate = Tickrate::new(self.tickrate.mode(), &self.gs);
         player_handle
     }
 
-    pub fn snapshot_inputs(&mut self) {
-        self.gs.inputs_prev.snapshot(&self.gs.players);
-        self.gs_fixed.inputs_prev.snapshot(&self.gs_fixed.players);
+    /// Replace the brains of all bots, cycling through `brain_names` (see `sys_ai::new_brain`).
+    pub fn set_brains(&mut self, brain_names: &[String]) -> Result<(), String> {
+        if brain_names.is_empty() {
+            return Err("No bot brains given".to_owned());
+        }
+        for (i, ai_handle) in self.gs.ais.iter_handles().into_iter().enumerate() {
+            let brain_name = &brain_names[i % brain_names.len()];
+            let brain = sys_ai::new_brain(brain_name, &mut self.gs.rng)?;
+            self.gs.ais[ai_handle].brain = brain;
+        }
+        self.tickrate = Tickrate::new(self.tickrate.mode(), &self.gs);
+        Ok(())
+    }
+
+    /// Whether the match is still being played but one of the limits has been reached.
+    pub fn match_should_end(&self, cvars: &Cvars) -> bool {
+        if !matches!(self.match_state, MatchState::Playing) {
+            return false;
+        }
+        if cvars.g_match_time_limit > 0.0 && self.gs.game_time >= cvars.g_match_time_limit {
+            return true;
+        }
+        cvars.g_match_points_limit > 0
+            && self
+                .gs
+                .players
+                .iter()
+                .any(|(_, player)| player.score.points(cvars) >= cvars.g_match_points_limit)
     }
 
-    pub fn input(&mut self, local_player_handle: Index, input: Input) {
-        // LATER Keep timestamps of input events. When splitting frame into multiple steps, update input each step.
-        self.gs.players[local_player_handle].input = input;
-        self.gs_fixed.players[local_player_handle].input = input;
+    /// End the match and let players vote which map from `options` is played next.
+    ///
+    /// Bots vote immediately. There has to be at least one option.
+    pub fn start_vote(&mut self, cvars: &Cvars, options: Vec<String>) {
+        assert!(!options.is_empty(), "No maps to vote for");
+        dbg_logf!("Match over, voting between: {:?}", options);
+        let mut votes = FnvHashMap::default();
+        for (_, ai) in self.gs.ais.iter_mut() {
+            let option = ai.brain.vote(&options).min(options.len() - 1);
+            votes.insert(ai.player, option);
+        }
+        self.match_state = MatchState::Voting(Vote {
+            options,
+            votes,
+            end_time: self.real_time + cvars.sv_map_vote_time,
+        });
+    }
+
+    /// Vote for the option with the given index, can be changed until the vote ends.
+    pub fn vote(&mut self, player_handle: Index, option: usize) {
+        if let MatchState::Voting(vote) = &mut self.match_state {
+            if option < vote.options.len() {
+                vote.votes.insert(player_handle, option);
+            }
+        }
+    }
+
+    /// Move the player's vote to the next (or previous) option.
+    pub fn step_vote(&mut self, player_handle: Index, forward: bool) {
+        if let MatchState::Voting(vote) = &self.match_state {
+            let option = vote.stepped(player_handle, forward);
+            self.vote(player_handle, option);
+        }
+    }
+
+    /// Queue the player's input which became current at `real_time`.
+    ///
+    /// It's applied by the first gamelogic tick at or after the corresponding game time
+    /// so when an update is split into multiple ticks, each sees the input from its time.
+    /// Call this before `update` with times since the previous update.
+    pub fn input(&mut self, cvars: &Cvars, player_handle: Index, real_time: f64, input: Input) {
+        let player = &self.gs.players[player_handle];
+        let input_prev = player
+            .input_events
+            .back()
+            .map_or(player.input, |&(_, input)| input);
+        // Handle pause here, gamelogic doesn't run while paused.
+        if !input_prev.pause && input.pause {
+            self.paused = !self.paused;
+        }
+
+        // The previous update simulated up to self.real_time, except for the time carried over.
+        let mut game_time = self.gs.game_time
+            + self.tickrate.dt_carry()
+            + (real_time - self.real_time) * cvars.d_speed;
+        let frozen = self.paused || !matches!(self.match_state, MatchState::Playing);
+        for gs in iter::once(&mut self.gs).chain(self.tickrate.gs_fixed_mut()) {
+            let events = &mut gs.players[player_handle].input_events;
+            if frozen {
+                // Game time doesn't advance, only the latest input matters.
+                events.clear();
+                game_time = gs.game_time;
+            }
+            events.push_back((game_time, input));
+        }
     }
 
     /// Run gamelogic frame(s) up to current time (in seconds).
@@ -128,86 +348,171 @@ impl Server {
         // https://medium.com/@tglaiel/how-to-make-your-game-run-at-60fps-24c61210fe75
 
         self.update_fps.tick(cvars.d_fps_period, self.real_time);
-        let start = macroquad::time::get_time();
+        // Not using macroquad's get_time() in the server because it needs a window
+        // and the server should also be able to run headless.
+        let start = macroquad::miniquad::date::now();
 
         // Update time tracking variables
         self.real_time_prev = self.real_time;
         self.real_time = real_time;
         self.real_time_delta = self.real_time - self.real_time_prev;
 
-        // Handle pause outside gamelogic so it works properly.
-        for (handle, player) in self.gs.players.iter() {
-            let input_prev = self.gs.inputs_prev.get(handle);
-            if !input_prev.pause && player.input.pause {
-                self.paused = !self.paused;
+        let mut dropped = 0.0;
+        match &self.match_state {
+            MatchState::Playing => {
+                if !self.paused {
+                    let dt_update = self.real_time_delta * cvars.d_speed;
+                    dropped = self.gamelogic(cvars, dt_update);
+                }
             }
+            MatchState::Voting(vote) => {
+                if self.real_time >= vote.end_time || vote.votes.len() >= self.gs.players.len() {
+                    let next_map = vote.options[vote.winner()].clone();
+                    dbg_logf!("Next map: {}", next_map);
+                    self.match_state = MatchState::Finished { next_map };
+                }
+            }
+            MatchState::Finished { .. } => {}
         }
-        if !self.paused {
-            let dt_update = self.real_time_delta * cvars.d_speed;
-            self.gamelogic(cvars, dt_update);
-        }
+        self.dropped_time += dropped;
+        self.dropped_durations.add(cvars.d_timing_samples, dropped);
 
-        let end = macroquad::time::get_time();
+        let end = macroquad::miniquad::date::now();
         self.update_durations
             .add(cvars.d_timing_samples, end - start);
     }
 
-    fn gamelogic(&mut self, cvars: &Cvars, dt_update: f64) {
-        // TODO prevent death spirals
-        // LATER impl the other modes
-        // TODO allow switching at runtime
-        match cvars.sv_tickrate_mode {
-            TickrateMode::Synchronized => {
+    /// With `TickrateMode::FixedWithInterpolation`, the state before the last tick
+    /// and how far (0 to 1) between it and `gs` entities should be rendered.
+    pub fn interpolation(&self, cvars: &Cvars) -> Option<(&GameState, f64)> {
+        match &self.tickrate {
+            Tickrate::FixedWithInterpolation { gs_prev, dt_carry } => {
+                // More than one tick is left over when the server can't keep up.
+                let alpha = (dt_carry * cvars.sv_tickrate_fixed_fps).min(1.0);
+                Some((gs_prev, alpha))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns how much game time was dropped because the server couldn't keep up.
+    fn gamelogic(&mut self, cvars: &Cvars, mut dt_update: f64) -> f64 {
+        if self.tickrate.mode() != cvars.sv_tickrate_mode {
+            // Continue from what players currently see, only make sure no time is lost.
+            dt_update += self.tickrate.dt_carry();
+            dbg_logf!(
+                "Tickrate mode {} -> {}",
+                self.tickrate.mode(),
+                cvars.sv_tickrate_mode
+            );
+            self.tickrate = Tickrate::new(cvars.sv_tickrate_mode, &self.gs);
+        }
+
+        // Prevent death spirals - don't even try to catch up after long stalls.
+        let mut dropped = 0.0;
+        if dt_update > cvars.sv_tickrate_max_catch_up {
+            dropped += dt_update - cvars.sv_tickrate_max_catch_up;
+            dt_update = cvars.sv_tickrate_max_catch_up;
+        }
+
+        // Taken out so gamelogic_tick can borrow self, always put back.
+        let tickrate = mem::replace(&mut self.tickrate, Tickrate::Synchronized);
+        self.tickrate = match tickrate {
+            Tickrate::Synchronized => {
                 let game_time_target = self.gs.game_time + dt_update;
                 self.gamelogic_tick(cvars, game_time_target);
+                Tickrate::Synchronized
             }
-            TickrateMode::Fixed => {
-                let game_time_target = self.gs.game_time + self.dt_carry + dt_update;
-                loop {
-                    // gs.game_time is still the previous frame here
-                    let remaining = game_time_target - self.gs.game_time;
-                    let dt = 1.0 / cvars.sv_tickrate_fixed_fps;
-                    if remaining < dt {
-                        self.dt_carry = remaining;
-                        break;
-                    }
-                    if cvars.d_tickrate_remaining {
-                        dbg_logf!("Remaining time: {}", remaining);
-                    }
-                    self.gamelogic_tick(cvars, self.gs.game_time + dt);
-                }
+            Tickrate::Fixed { dt_carry } => Tickrate::Fixed {
+                dt_carry: self.gamelogic_fixed(cvars, dt_carry, dt_update, &mut dropped, None),
+            },
+            Tickrate::FixedWithInterpolation {
+                mut gs_prev,
+                dt_carry,
+            } => {
+                let gs_prev_mut = Some(gs_prev.as_mut());
+                let dt_carry =
+                    self.gamelogic_fixed(cvars, dt_carry, dt_update, &mut dropped, gs_prev_mut);
+                Tickrate::FixedWithInterpolation { gs_prev, dt_carry }
             }
-            TickrateMode::FixedOrSmaller => {
-                // TODO Input is ignored or duplicated depending on fixed FPS
-                // http://localhost:8000/web/?map=Atrium&bots_max=5&sv_gamelogic_mode=2&sv_gamelogic_fixed_fps=90
-                // TODO Related: gs_fixed should only be used here, the rest of the code shouldn't know about it.
-
-                let dt_fixed = self.gs.game_time - self.gs_fixed.game_time;
-                let game_time_target = self.gs_fixed.game_time + dt_fixed + dt_update;
-                self.gs = self.gs_fixed.clone();
+            Tickrate::FixedOrSmaller { gs_fixed, dt_carry } => {
+                let game_time_target = self.gs.game_time + dt_carry + dt_update;
+                self.gs = *gs_fixed;
                 let mut remaining;
-                loop {
+                let mut ticks = 0;
+                let overload = loop {
                     // gs.game_time is still the previous frame here
                     remaining = game_time_target - self.gs.game_time;
                     let dt = 1.0 / cvars.sv_tickrate_fixed_fps;
                     if remaining < dt {
-                        self.gs_fixed = self.gs.clone();
-                        break;
+                        break false;
+                    }
+                    if ticks == cvars.sv_tickrate_max_ticks {
+                        break true;
                     }
                     self.gamelogic_tick(cvars, self.gs.game_time + dt);
+                    ticks += 1;
+                };
+                let gs_fixed = Box::new(self.gs.clone());
+                if overload {
+                    // Already behind, no point in simulating the smaller step.
+                    Tickrate::FixedOrSmaller {
+                        gs_fixed,
+                        dt_carry: overloaded(cvars, remaining, &mut dropped),
+                    }
+                } else {
+                    if cvars.d_tickrate_remaining {
+                        dbg_logf!("Remaining time: {}", remaining);
+                    }
+                    self.gamelogic_tick(cvars, self.gs.game_time + remaining);
+                    // TODO skip too small steps?
+                    Tickrate::FixedOrSmaller {
+                        gs_fixed,
+                        dt_carry: 0.0,
+                    }
                 }
-                if cvars.d_tickrate_remaining {
-                    dbg_logf!("Remaining time: {}", remaining);
-                }
-                self.gamelogic_tick(cvars, self.gs.game_time + remaining);
-                // TODO skip too small steps?
             }
-        }
+        };
         // TODO don't use game_time here?
+        dropped
+    }
+
+    /// Run as many fixed ticks as fit into the carried over and new time, returns the time left over.
+    ///
+    /// `gs_prev` is updated to the state before the last tick.
+    fn gamelogic_fixed(
+        &mut self,
+        cvars: &Cvars,
+        dt_carry: f64,
+        dt_update: f64,
+        dropped: &mut f64,
+        mut gs_prev: Option<&mut GameState>,
+    ) -> f64 {
+        let game_time_target = self.gs.game_time + dt_carry + dt_update;
+        let mut ticks = 0;
+        loop {
+            // gs.game_time is still the previous frame here
+            let remaining = game_time_target - self.gs.game_time;
+            let dt = 1.0 / cvars.sv_tickrate_fixed_fps;
+            if remaining < dt {
+                return remaining;
+            }
+            if ticks == cvars.sv_tickrate_max_ticks {
+                return overloaded(cvars, remaining, dropped);
+            }
+            if cvars.d_tickrate_remaining {
+                dbg_logf!("Remaining time: {}", remaining);
+            }
+            if let Some(gs_prev) = &mut gs_prev {
+                gs_prev.clone_from(&self.gs);
+            }
+            self.gamelogic_tick(cvars, self.gs.game_time + dt);
+            ticks += 1;
+        }
     }
 
     fn gamelogic_tick(&mut self, cvars: &Cvars, game_time: f64) {
-        let start = macroquad::time::get_time();
+        let start = macroquad::miniquad::date::now();
         self.gamelogic_fps.tick(cvars.d_fps_period, self.real_time);
 
         // Update time tracking variables (in seconds)
@@ -227,9 +532,14 @@ impl Server {
         dbg_textd!(self.gs.game_time);
         dbg_textd!(self.gs.game_time_prev);
 
+        self.gs.inputs_prev.snapshot(&self.gs.players);
+        for (_, player) in self.gs.players.iter_mut() {
+            player.apply_input_events(game_time);
+        }
+
         systems::cleanup(cvars, &mut self.gs);
 
-        sys_ai::ai(cvars, &mut self.gs);
+        sys_ai::ai(cvars, &mut self.gs, &self.map);
 
         systems::respawning(cvars, &mut self.gs, &self.map);
 
@@ -255,8 +565,472 @@ impl Server {
         dbg_textf!("projectile count: {}", self.gs.projectiles.len());
         dbg_textf!("explosion count: {}", self.gs.explosions.len());
 
-        let end = macroquad::time::get_time();
+        let end = macroquad::miniquad::date::now();
         self.gamelogic_durations
             .add(cvars.d_timing_samples, end - start);
     }
 }
+
+/// Handle time left over after running `sv_tickrate_max_ticks`,
+/// returns how much of it to carry over to the next update.
+fn overloaded(cvars: &Cvars, remaining: f64, dropped: &mut f64) -> f64 {
+    let dt_carry = match cvars.sv_tickrate_overload {
+        TickrateOverload::Drop => 0.0,
+        TickrateOverload::SlowDown => remaining.min(cvars.sv_tickrate_max_catch_up),
+    };
+    *dropped += remaining - dt_carry;
+    dt_carry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        f64::consts::PI,
+        fs,
+        panic::{self, AssertUnwindSafe},
+    };
+
+    use strum::IntoEnumIterator;
+    use thunderdome::Arena;
+    use vek::Wrap;
+
+    use crate::{
+        cvars::{Bounds, CvarFlags, TurretTurnMode},
+        map,
+        map_rotation::MapRotation,
+    };
+
+    fn load_atrium() -> Map {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = map::load_tex_list(&tex_list_text).unwrap();
+        let map_text = fs::read_to_string("maps/Atrium.map").unwrap();
+        map::load_map(&map_text, surfaces).unwrap()
+    }
+
+    #[test]
+    fn test_match_end_and_vote() {
+        let map = load_atrium();
+
+        let cvars = Cvars {
+            d_seed: 42,
+            g_match_time_limit: 1.0,
+            sv_map_vote_time: 5.0,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, map);
+        let player_handle = server.connect(&cvars, "Player 1");
+
+        let mut real_time = 0.0;
+        while !server.match_should_end(&cvars) {
+            real_time += 1.0 / 60.0;
+            server.update(&cvars, real_time);
+        }
+        assert!(server.gs.game_time >= 1.0);
+
+        let options = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        server.start_vote(&cvars, options);
+        assert!(!server.match_should_end(&cvars));
+        let game_time = server.gs.game_time;
+
+        // Bots already voted, the vote ends when the last player votes.
+        server.vote(player_handle, 2);
+        server.vote(player_handle, 99);
+        match &server.match_state {
+            MatchState::Voting(vote) => {
+                assert_eq!(vote.votes.len(), server.gs.players.len());
+                assert_eq!(vote.votes[&player_handle], 2);
+            }
+            _ => panic!("expected voting"),
+        }
+        real_time += 1.0 / 60.0;
+        server.update(&cvars, real_time);
+        assert!(matches!(server.match_state, MatchState::Finished { .. }));
+        // The game is frozen after the match.
+        assert_eq!(server.gs.game_time, game_time);
+
+        let vote = Vote {
+            options: vec!["a".to_owned(), "b".to_owned()],
+            votes: FnvHashMap::default(),
+            end_time: 0.0,
+        };
+        assert_eq!(vote.winner(), 0);
+    }
+
+    #[test]
+    fn test_vote_stepped() {
+        let mut players = Arena::new();
+        let player1 = players.insert(());
+        let player2 = players.insert(());
+        let mut vote = Vote {
+            options: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            votes: FnvHashMap::default(),
+            end_time: 0.0,
+        };
+        assert_eq!(vote.stepped(player1, true), 0);
+        assert_eq!(vote.stepped(player1, false), 2);
+
+        vote.votes.insert(player1, 2);
+        vote.votes.insert(player2, 0);
+        assert_eq!(vote.stepped(player1, true), 0);
+        assert_eq!(vote.stepped(player1, false), 1);
+        assert_eq!(vote.stepped(player2, true), 1);
+        assert_eq!(vote.stepped(player2, false), 2);
+    }
+
+    #[test]
+    fn test_switch_tickrate_mode() {
+        let mut cvars = Cvars {
+            d_seed: 42,
+            // Doesn't divide the frame time so there's always something left over.
+            sv_tickrate_fixed_fps: 25.0,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, load_atrium());
+        let player_handle = server.connect(&cvars, "Player 1");
+
+        let modes: Vec<_> = TickrateMode::iter().collect();
+        let frame_time = 1.0 / 60.0;
+        let mut real_time = 0.0;
+        for i in 0..120 {
+            // Every order of modes, including switching back and forth.
+            cvars.sv_tickrate_mode = modes[(i / 7 + i / 23) % modes.len()];
+            real_time += frame_time;
+            let input = Input {
+                fire: i % 2 == 0,
+                ..Input::default()
+            };
+            let real_time_prev = server.real_time;
+            server.input(&cvars, player_handle, real_time_prev, input);
+            server.update(&cvars, real_time);
+
+            // No time is lost or simulated twice, just up to one tick left over with Fixed modes.
+            let behind = real_time - server.gs.game_time;
+            if matches!(
+                cvars.sv_tickrate_mode,
+                TickrateMode::Fixed | TickrateMode::FixedWithInterpolation
+            ) {
+                let dt = 1.0 / cvars.sv_tickrate_fixed_fps;
+                assert!((-1e-9..dt).contains(&behind), "{i}: {behind}");
+            } else {
+                assert!(behind.abs() < 1e-9, "{i}: {behind}");
+            }
+            // Applied unless there was no tick this frame.
+            let player = &server.gs.players[player_handle];
+            let latest = player
+                .input_events
+                .back()
+                .map_or(player.input, |&(_, input)| input);
+            assert_eq!(latest.fire, input.fire);
+            if let Some(gs_fixed) = server.tickrate.gs_fixed_mut() {
+                assert!(gs_fixed.players.contains(player_handle));
+                assert!(gs_fixed.game_time <= server.gs.game_time);
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_events() {
+        let tap = Input {
+            next_weapon: true,
+            ..Input::default()
+        };
+        for (mode, frame_time) in [
+            // Several ticks per frame - the tap must not be seen by each of them.
+            (TickrateMode::Fixed, 1.0 / 20.0),
+            // One tick per frame - pressing and releasing within it must not be lost.
+            (TickrateMode::Synchronized, 1.0 / 60.0),
+        ] {
+            let cvars = Cvars {
+                d_seed: 42,
+                sv_tickrate_mode: mode,
+                sv_tickrate_fixed_fps: 100.0,
+                ..Cvars::default()
+            };
+            let mut server = Server::new(&cvars, load_atrium());
+            let player_handle = server.connect(&cvars, "Player 1");
+            let weapon = |server: &Server| server.gs.players[player_handle].cur_weapon as u8;
+
+            let mut real_time = 0.0;
+            for frame in 0..6 {
+                let real_time_prev = real_time;
+                real_time += frame_time;
+                match frame {
+                    // Tapped within one frame.
+                    1 => {
+                        server.input(&cvars, player_handle, real_time_prev, tap);
+                        let released_time = real_time_prev + frame_time / 2.0;
+                        server.input(&cvars, player_handle, released_time, Input::new());
+                    }
+                    // Held for two frames.
+                    3 => server.input(&cvars, player_handle, real_time_prev, tap),
+                    5 => server.input(&cvars, player_handle, real_time_prev, Input::new()),
+                    _ => {}
+                }
+                server.update(&cvars, real_time);
+
+                let expected = match frame {
+                    0 => 0,
+                    1..=2 => 1,
+                    _ => 2,
+                };
+                assert_eq!(weapon(&server), expected, "{mode} frame {frame}");
+            }
+            assert!(!server.gs.players[player_handle].input.next_weapon);
+        }
+    }
+
+    #[test]
+    fn test_turret_angle() {
+        let cvars = Cvars {
+            d_seed: 42,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, load_atrium());
+        let player_handle = server.connect(&cvars, "Player 1");
+        let vehicle_handle = server.gs.players[player_handle].vehicle.unwrap();
+        let vehicle = &server.gs.vehicles[vehicle_handle];
+        // Behind and slightly to the right, the turret should turn right.
+        let wanted = (vehicle.angle + 170_f64.to_radians()).rem_euclid(2.0 * PI);
+        let input = Input {
+            turret_angle: Some(wanted),
+            // Steps are overridden.
+            turret_left: true,
+            ..Input::default()
+        };
+
+        let speed = cvars.g_turret_turn_speed_deg.to_radians();
+        let mut real_time = 0.0;
+        let mut prev: f64 = 0.0;
+        for _ in 0..180 {
+            server.input(&cvars, player_handle, real_time, input);
+            real_time += 1.0 / 60.0;
+            server.update(&cvars, real_time);
+            let vehicle = &server.gs.vehicles[vehicle_handle];
+            let turned = prev.delta_angle(vehicle.turret_angle_current);
+            assert!(turned >= 0.0, "{turned}");
+            assert!(turned <= speed / 60.0 + 1e-9, "{turned}");
+            prev = vehicle.turret_angle_current;
+        }
+        let vehicle = &server.gs.vehicles[vehicle_handle];
+        let world_angle = vehicle.angle + vehicle.turret_angle_current;
+        assert!(world_angle.delta_angle(wanted).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_turret_turn_mode() {
+        let left = Input {
+            turret_left: true,
+            ..Input::default()
+        };
+        let right = Input {
+            turret_right: true,
+            ..Input::default()
+        };
+        let step = Cvars::default().g_turret_turn_step_angle_deg;
+        for (mode, taps, min, max) in [
+            (
+                TurretTurnMode::Queue,
+                vec![left, left],
+                -2.0 * step,
+                -2.0 * step,
+            ),
+            (
+                TurretTurnMode::Cancel,
+                vec![left, left],
+                -2.0 * step,
+                -2.0 * step,
+            ),
+            (TurretTurnMode::Queue, vec![left, left, right], -step, -step),
+            // The turret has turned a bit left before right cancels the rest.
+            (
+                TurretTurnMode::Cancel,
+                vec![left, left, right],
+                1.0,
+                step - 1.0,
+            ),
+            (TurretTurnMode::Queue, vec![left, right, right], step, step),
+            (
+                TurretTurnMode::Cancel,
+                vec![left, right, right],
+                step + 1.0,
+                2.0 * step - 1.0,
+            ),
+        ] {
+            let cvars = Cvars {
+                d_seed: 42,
+                g_turret_turn_mode: mode,
+                ..Cvars::default()
+            };
+            let mut server = Server::new(&cvars, load_atrium());
+            let player_handle = server.connect(&cvars, "Player 1");
+            let vehicle_handle = server.gs.players[player_handle].vehicle.unwrap();
+
+            // Each tap is one frame pressed, one released.
+            let mut real_time = 0.0;
+            let inputs = taps.iter().flat_map(|&tap| [tap, Input::new()]);
+            for input in inputs.chain([Input::new(); 180]) {
+                server.input(&cvars, player_handle, real_time, input);
+                real_time += 1.0 / 60.0;
+                server.update(&cvars, real_time);
+            }
+
+            let vehicle = &server.gs.vehicles[vehicle_handle];
+            let angle = 0.0_f64
+                .delta_angle(vehicle.turret_angle_current)
+                .to_degrees();
+            assert!(
+                min - 1e-6 <= angle && angle <= max + 1e-6,
+                "{mode} {taps:?}: {angle}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let cvars = Cvars {
+            d_seed: 42,
+            sv_tickrate_mode: TickrateMode::FixedWithInterpolation,
+            sv_tickrate_fixed_fps: 25.0,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, load_atrium());
+        assert!(server.interpolation(&cvars).is_some());
+
+        for frame in 1..=60 {
+            let game_time = server.gs.game_time;
+            server.update(&cvars, frame as f64 / 60.0);
+
+            let (gs_prev, alpha) = server.interpolation(&cvars).unwrap();
+            assert!((0.0..1.0).contains(&alpha), "{frame}: {alpha}");
+            let behind = frame as f64 / 60.0 - server.gs.game_time;
+            assert!((alpha - behind * 25.0).abs() < 1e-9);
+            if server.gs.game_time > 0.0 {
+                // Exactly one tick apart so there's something to interpolate.
+                let tick = server.gs.game_time - gs_prev.game_time;
+                assert!((tick - 1.0 / 25.0).abs() < 1e-9, "{frame}: {tick}");
+            }
+            if server.gs.game_time == game_time {
+                // No tick this frame, only alpha moves.
+                assert!(alpha > 0.0);
+            }
+        }
+
+        let cvars = Cvars {
+            sv_tickrate_mode: TickrateMode::Fixed,
+            ..cvars
+        };
+        server.update(&cvars, 61.0 / 60.0);
+        assert!(server.interpolation(&cvars).is_none());
+    }
+
+    #[test]
+    fn test_death_spiral() {
+        for mode in TickrateMode::iter() {
+            for overload in TickrateOverload::iter() {
+                let cvars = Cvars {
+                    d_seed: 42,
+                    sv_tickrate_mode: mode,
+                    sv_tickrate_fixed_fps: 100.0,
+                    sv_tickrate_max_catch_up: 0.25,
+                    sv_tickrate_max_ticks: 5,
+                    sv_tickrate_overload: overload,
+                    ..Cvars::default()
+                };
+                let mut server = Server::new(&cvars, load_atrium());
+
+                // A frame, a long stall and then normal frames again.
+                let mut real_times = vec![1.0 / 60.0, 10.0];
+                real_times.extend((1..=30).map(|i| 10.0 + i as f64 / 60.0));
+                for real_time in real_times {
+                    let game_time_before = server.gs.game_time;
+                    server.update(&cvars, real_time);
+
+                    // Never more work than the limits allow - 5 full ticks and a smaller one at most.
+                    let dt = server.gs.game_time - game_time_before;
+                    let dt_max = if mode == TickrateMode::Synchronized {
+                        0.25
+                    } else {
+                        0.06
+                    };
+                    assert!(dt <= dt_max + 1e-9, "{mode} {overload}: {dt}");
+
+                    // All time is either simulated, dropped or waiting to be simulated.
+                    let total =
+                        server.gs.game_time + server.dropped_time + server.tickrate.dt_carry();
+                    assert!((total - real_time).abs() < 1e-9, "{mode} {overload}");
+                }
+
+                assert!(server.dropped_time > 9.0);
+                // Caught up eventually.
+                assert!(server.tickrate.dt_carry() < 0.01);
+            }
+        }
+    }
+
+    /// Play a bit and then vote for the next map.
+    fn simulate_match(cvars: &Cvars, map: Map) {
+        let mut server = Server::new(cvars, map);
+        // Count frames, not game time, in case the game is slowed down or stopped.
+        for frame in 1..=30 {
+            if frame == 20 {
+                let maps = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+                let options = MapRotation::new(maps, 0).vote_options(cvars.sv_map_vote_options);
+                server.start_vote(cvars, options);
+            }
+            server.update(cvars, frame as f64 / 60.0);
+        }
+
+        // NaNs don't crash but silently break everything they touch.
+        for (_, vehicle) in server.gs.vehicles.iter() {
+            assert!(vehicle.pos.x.is_finite() && vehicle.pos.y.is_finite());
+            assert!(vehicle.angle.is_finite() && vehicle.hp_fraction.is_finite());
+        }
+        for (_, projectile) in server.gs.projectiles.iter() {
+            assert!(projectile.pos.x.is_finite() && projectile.pos.y.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_cvar_boundaries() {
+        // Any value a cvar accepts can make the game weird but must not crash it.
+        let map = load_atrium();
+        let mut failures = Vec::new();
+        for name in Cvars::names() {
+            // Not used by the simulation.
+            if CvarFlags::of(&name).client {
+                continue;
+            }
+            let mut values = vec![0.0, -1.0, 1000.0];
+            if let Some(bounds) = Bounds::of(&name) {
+                values.extend(bounds.limits());
+            }
+            // Only the tickrate cvars are worth testing with every mode, it'd be too slow otherwise.
+            let modes: Vec<_> = if name.starts_with("sv_tickrate") {
+                TickrateMode::iter().collect()
+            } else {
+                vec![Cvars::default().sv_tickrate_mode]
+            };
+            for value in values {
+                for &mode in &modes {
+                    let mut cvars = Cvars {
+                        d_seed: 42,
+                        sv_tickrate_mode: mode,
+                        ..Cvars::default()
+                    };
+                    if cvars.set_value(&name, &value.to_string()).is_err() {
+                        continue;
+                    }
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                        simulate_match(&cvars, map.clone())
+                    }));
+                    if res.is_err() {
+                        failures.push(format!("{name} {value} ({mode})"));
+                    }
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{failures:?}");
+    }
+}
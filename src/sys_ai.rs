@@ -1,63 +1,248 @@
-//! Stub. So far bots move and shoot randomly.
+//! Bot AI. Each bot has a brain which decides what it should press each frame.
+//!
+//! So far the only real brain moves and shoots randomly.
+//! To write a new one, implement `BotBrain` and add it to `new_brain`,
+//! then select it using the `--brains` command line option.
 
-use rand::Rng;
+use std::{fmt::Debug, mem};
+
+use rand::{prelude::SmallRng, Rng, SeedableRng};
+use thunderdome::Index;
 
 use crate::{
     cvars::Cvars,
+    entities::{Player, Vehicle},
     game_state::{GameState, Input},
+    map::Map,
 };
 
-pub fn ai(cvars: &Cvars, gs: &mut GameState) {
+/// The decision making part of a bot.
+///
+/// Brains are stored in `GameState` and get cloned with it (e.g. by `TickrateMode::FixedOrSmaller`)
+/// so all their state should live inside them - that includes their RNG if they need one.
+pub trait BotBrain: Debug {
+    /// Name used to select the brain on the command line.
+    fn name(&self) -> &'static str;
+
+    /// Decide what the bot's player should press this frame.
+    fn think(&mut self, view: &BotView) -> Input;
+
+    /// Pick the next map at the end of a match - returns an index into `options`.
+    ///
+    /// The first option is the next map in rotation.
+    fn vote(&mut self, _options: &[String]) -> usize {
+        0
+    }
+
+    /// Brains need to be clonable but `Clone` isn't object safe.
+    fn clone_box(&self) -> Box<dyn BotBrain>;
+}
+
+impl Clone for Box<dyn BotBrain> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Read-only view of the game from the perspective of one bot.
+#[derive(Debug, Clone, Copy)]
+pub struct BotView<'a> {
+    pub cvars: &'a Cvars,
+    pub gs: &'a GameState,
+    pub map: &'a Map,
+    pub player_handle: Index,
+}
+
+impl<'a> BotView<'a> {
+    pub fn player(&self) -> &'a Player {
+        &self.gs.players[self.player_handle]
+    }
+
+    pub fn vehicle(&self) -> &'a Vehicle {
+        &self.gs.vehicles[self.player().vehicle.unwrap()]
+    }
+}
+
+/// Create a brain by its name.
+///
+/// The brain gets its own RNG seeded from `rng` so it doesn't affect the rest of the gamelogic.
+pub fn new_brain(name: &str, rng: &mut SmallRng) -> Result<Box<dyn BotBrain>, String> {
+    match name {
+        "idle" => Ok(Box::new(IdleBrain)),
+        "random" => Ok(Box::new(RandomBrain::new(SmallRng::seed_from_u64(
+            rng.gen(),
+        )))),
+        _ => Err(format!("Bot brain named {} not found", name)),
+    }
+}
+
+pub fn ai(cvars: &Cvars, gs: &mut GameState, map: &Map) {
     if !cvars.ai {
         return;
     }
 
-    for (_, ai) in gs.ais.iter_mut() {
-        let player = &mut gs.players[ai.player];
-        let vehicle = &gs.vehicles[player.vehicle.unwrap()];
+    // Borrowck dance - brains need to see the whole game state
+    // while we're mutating them so take them out for a moment.
+    let mut ais = mem::take(&mut gs.ais);
+    for (_, ai) in ais.iter_mut() {
+        let view = BotView {
+            cvars,
+            gs,
+            map,
+            player_handle: ai.player,
+        };
+        let input = ai.brain.think(&view);
+        gs.players[ai.player].input = input;
+    }
+    gs.ais = ais;
+}
+
+/// Never presses anything - useful as stationary targets or a baseline to compare other brains against.
+#[derive(Debug, Clone)]
+pub struct IdleBrain;
+
+impl BotBrain for IdleBrain {
+    fn name(&self) -> &'static str {
+        "idle"
+    }
+
+    fn think(&mut self, _view: &BotView) -> Input {
+        Input::new()
+    }
+
+    fn clone_box(&self) -> Box<dyn BotBrain> {
+        Box::new(self.clone())
+    }
+}
+
+/// Stub. Moves and shoots randomly.
+#[derive(Debug, Clone)]
+pub struct RandomBrain {
+    rng: SmallRng,
+    movement: i32,
+    turning: i32,
+    firing: bool,
+}
+
+impl RandomBrain {
+    pub fn new(rng: SmallRng) -> Self {
+        Self {
+            rng,
+            movement: 0,
+            turning: 0,
+            firing: false,
+        }
+    }
+}
+
+impl BotBrain for RandomBrain {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn think(&mut self, view: &BotView) -> Input {
+        let vehicle = view.vehicle();
 
         // keep moving forward if recently spawned
-        let age = gs.game_time - vehicle.spawn_time;
+        let age = view.gs.game_time - vehicle.spawn_time;
         if age < 0.5 {
-            ai.movement = 1;
-        } else if gs.rng.gen_bool(0.01) {
-            let r: f64 = gs.rng.gen();
+            self.movement = 1;
+        } else if self.rng.gen_bool(0.01) {
+            let r: f64 = self.rng.gen();
             if r < 0.5 {
-                ai.movement = 1;
+                self.movement = 1;
             } else if r < 0.65 {
-                ai.movement = 0;
+                self.movement = 0;
             } else {
-                ai.movement = -1;
+                self.movement = -1;
             }
         }
 
-        if gs.rng.gen_bool(0.03) {
-            ai.turning = gs.rng.gen_range(-1..=1);
+        if self.rng.gen_bool(0.03) {
+            self.turning = self.rng.gen_range(-1..=1);
         }
 
-        if !ai.firing && gs.rng.gen_bool(0.01) {
-            ai.firing = true;
-        } else if ai.firing && gs.rng.gen_bool(0.03) {
-            ai.firing = false;
+        if !self.firing && self.rng.gen_bool(0.01) {
+            self.firing = true;
+        } else if self.firing && self.rng.gen_bool(0.03) {
+            self.firing = false;
         }
 
-        dbg_world_textf!(vehicle.pos, "{:.1} {}", age, ai.movement);
-
-        player.input = Input {
-            up: ai.movement == 1,
-            down: ai.movement == -1,
-            left: ai.turning == -1,
-            right: ai.turning == 1,
-            turret_left: gs.rng.gen_bool(0.01),
-            turret_right: gs.rng.gen_bool(0.01),
-            prev_weapon: gs.rng.gen_bool(0.02),
-            next_weapon: gs.rng.gen_bool(0.01),
-            fire: ai.firing,
-            mine: gs.rng.gen_bool(0.001),
-            self_destruct: gs.rng.gen_bool(0.0001),
-            horn: gs.rng.gen_bool(0.0001),
+        dbg_world_textf!(vehicle.pos, "{:.1} {}", age, self.movement);
+
+        Input {
+            up: self.movement == 1,
+            down: self.movement == -1,
+            left: self.turning == -1,
+            right: self.turning == 1,
+            turret_left: self.rng.gen_bool(0.01),
+            turret_right: self.rng.gen_bool(0.01),
+            prev_weapon: self.rng.gen_bool(0.02),
+            next_weapon: self.rng.gen_bool(0.01),
+            fire: self.firing,
+            mine: self.rng.gen_bool(0.001),
+            self_destruct: self.rng.gen_bool(0.0001),
+            horn: self.rng.gen_bool(0.0001),
             chat: false,
             pause: false, // :)
+            turret_angle: None,
+        }
+    }
+
+    fn vote(&mut self, options: &[String]) -> usize {
+        self.rng.gen_range(0..options.len())
+    }
+
+    fn clone_box(&self) -> Box<dyn BotBrain> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::{map, server::Server};
+
+    #[test]
+    fn test_headless_brains() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = map::load_tex_list(&tex_list_text).unwrap();
+        let map_text = fs::read_to_string("maps/Atrium.map").unwrap();
+        let map = map::load_map(&map_text, surfaces).unwrap();
+
+        let cvars = Cvars {
+            d_seed: 42,
+            ..Cvars::default()
+        };
+        let mut server = Server::new(&cvars, map);
+        server
+            .set_brains(&["idle".to_owned(), "random".to_owned()])
+            .unwrap();
+        assert!(server.set_brains(&["nonexistent".to_owned()]).is_err());
+
+        let mut real_time = 0.0;
+        while server.gs.game_time < 2.0 {
+            real_time += 1.0 / 60.0;
+            server.update(&cvars, real_time);
+        }
+
+        let mut idle_cnt = 0;
+        let mut random_cnt = 0;
+        for (_, ai) in server.gs.ais.iter() {
+            let input = server.gs.players[ai.player].input;
+            match ai.brain.name() {
+                "idle" => {
+                    assert_eq!(format!("{:?}", input), "Input { }");
+                    idle_cnt += 1;
+                }
+                "random" => random_cnt += 1,
+                _ => unreachable!(),
+            }
         }
+        assert_ne!(idle_cnt, 0);
+        assert_ne!(random_cnt, 0);
     }
 }
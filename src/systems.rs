@@ -12,8 +12,8 @@ use thunderdome::Index;
 use vek::{Clamp, LineSegment2, Wrap};
 
 use crate::{
-    cvars::{Cvars, Hardpoint, MovementStats},
-    entities::{Ammo, Projectile, Respawn, Vehicle, VehicleType, Weapon, WEAPS_CNT},
+    cvars::{Cvars, Hardpoint, MovementStats, TurretTurnMode},
+    entities::{Ammo, Projectile, Respawn, Vehicle, VehicleType, Weapon, VEHICLES_CNT, WEAPS_CNT},
     game_state::ArenaExt,
     game_state::{Explosion, GameState, Input, RailBeam},
     map::{F64Ext, Map, Vec2f},
@@ -45,7 +45,8 @@ pub fn respawning(cvars: &Cvars, gs: &mut GameState, map: &Map) {
         // Require the whole press and release cycle to happen while dead
         // so releasing fire after dying doesn't respawn immediately
         // even if respawn delay is 0.
-        // LATER Allow press and release in one frame.
+        // Pressing and releasing within one frame also works,
+        // the release is applied a tick later (see `Player::apply_input_events`).
 
         if !input_prev.fire && player.input.fire {
             player.respawn = Respawn::Pressed;
@@ -72,7 +73,7 @@ pub fn spawn_vehicle(
     player_handle: Index,
     use_spawns: bool,
 ) {
-    let veh_type = VehicleType::n(gs.rng.gen_range(0..3)).unwrap();
+    let veh_type = VehicleType::n(gs.rng.gen_range(0..VEHICLES_CNT as i64)).unwrap();
     let (spawn_pos, spawn_angle) = if use_spawns {
         map.random_spawn(&mut gs.rng)
     } else {
@@ -135,7 +136,7 @@ pub fn self_destruct(cvars: &Cvars, gs: &mut GameState) {
 
 pub fn vehicle_movement(cvars: &Cvars, gs: &mut GameState, map: &Map) {
     for (_, vehicle) in gs.vehicles.iter_mut() {
-        let stats = cvars.g_vehicle_movement_stats(vehicle.veh_type);
+        let stats = cvars.g_vehicle(vehicle.veh_type).movement_stats();
 
         // No movement after death or when guiding
         let input = if vehicle.destroyed() {
@@ -270,12 +271,25 @@ pub fn vehicle_logic(cvars: &Cvars, gs: &mut GameState) {
         let input_prev = gs.inputs_prev.get(vehicle.owner);
 
         // Turret turning
-        if !input_prev.turret_left && player.input.turret_left {
+        let pressed_left = !input_prev.turret_left && player.input.turret_left;
+        let pressed_right = !input_prev.turret_right && player.input.turret_right;
+        if cvars.g_turret_turn_mode == TurretTurnMode::Cancel {
+            let remaining = vehicle
+                .turret_angle_current
+                .delta_angle(vehicle.turret_angle_wanted);
+            if (pressed_left && remaining > 0.0) || (pressed_right && remaining < 0.0) {
+                vehicle.turret_angle_wanted = vehicle.turret_angle_current;
+            }
+        }
+        if pressed_left {
             vehicle.turret_angle_wanted -= cvars.g_turret_turn_step_angle_deg.to_radians();
         }
-        if !input_prev.turret_right && player.input.turret_right {
+        if pressed_right {
             vehicle.turret_angle_wanted += cvars.g_turret_turn_step_angle_deg.to_radians();
         }
+        if let Some(angle) = player.input.turret_angle {
+            vehicle.turret_angle_wanted = angle - vehicle.angle;
+        }
         vehicle.turret_angle_wanted = vehicle.turret_angle_wanted.rem_euclid(2.0 * PI);
 
         let delta = vehicle
@@ -290,7 +304,7 @@ pub fn vehicle_logic(cvars: &Cvars, gs: &mut GameState) {
         let ammo = &mut vehicle.ammos[player.cur_weapon as usize];
         if let Ammo::Reloading(_, end) = ammo {
             if gs.game_time >= *end {
-                *ammo = Ammo::Loaded(gs.game_time, cvars.g_weapon_reload_ammo(player.cur_weapon));
+                *ammo = Ammo::Loaded(gs.game_time, cvars.g_weapon(player.cur_weapon).reload_ammo);
             }
         }
     }
@@ -310,10 +324,10 @@ pub fn shooting(cvars: &Cvars, gs: &mut GameState) {
                 continue;
             }
 
-            *ready_time = gs.game_time + cvars.g_weapon_refire(player.cur_weapon);
+            *ready_time = gs.game_time + cvars.g_weapon(player.cur_weapon).refire;
             *count -= 1;
             if *count == 0 {
-                let reload_time = cvars.g_weapon_reload_time(player.cur_weapon);
+                let reload_time = cvars.g_weapon(player.cur_weapon).reload_time;
                 *ammo = Ammo::Reloading(gs.game_time, gs.game_time + reload_time);
             }
 
@@ -326,18 +340,20 @@ pub fn shooting(cvars: &Cvars, gs: &mut GameState) {
                 }
                 Hardpoint::Turret => {
                     shot_angle = vehicle.angle + vehicle.turret_angle_current;
-                    let turret_offset = cvars.g_vehicle_turret_offset_chassis(vehicle.veh_type);
+                    let turret_offset = cvars.g_vehicle(vehicle.veh_type).turret_offset_chassis();
                     shot_origin = vehicle.pos
                         + turret_offset.rotated_z(vehicle.angle)
                         + weapon_offset.rotated_z(shot_angle);
                 }
             }
 
-            // Some sane defaults to be overriden later
+            // Most weapons shoot one projectile straight ahead, the rest is overriden later
+            let stats = cvars.g_weapon(player.cur_weapon);
             let mut projectile = Projectile {
-                weapon: Weapon::Mg,
+                weapon: player.cur_weapon,
                 pos: shot_origin,
-                vel: Vec2f::zero(),
+                vel: Vec2f::new(stats.speed, 0.0).rotated_z(shot_angle)
+                    + stats.vehicle_velocity_factor * vehicle.vel,
                 angle: shot_angle,
                 turn_rate: 0.0,
                 explode_time: f64::MAX,
@@ -350,21 +366,13 @@ pub fn shooting(cvars: &Cvars, gs: &mut GameState) {
                     let spread = cvars.g_machine_gun_angle_spread * r;
                     // Using spread as shot_vel.y would mean the resulting spread depends on speed
                     // so it's better to use spread on angle.
-                    projectile.vel = Vec2f::new(cvars.g_machine_gun_speed, 0.0)
-                        .rotated_z(shot_angle + spread)
-                        + cvars.g_machine_gun_vehicle_velocity_factor * vehicle.vel;
-                    gs.projectiles.insert(projectile);
-                }
-                Weapon::Rail => {
-                    projectile.weapon = Weapon::Rail;
-                    projectile.vel = Vec2f::new(cvars.g_railgun_speed, 0.0).rotated_z(shot_angle)
-                        + cvars.g_railgun_vehicle_velocity_factor * vehicle.vel;
+                    projectile.vel = Vec2f::new(stats.speed, 0.0).rotated_z(shot_angle + spread)
+                        + stats.vehicle_velocity_factor * vehicle.vel;
                     gs.projectiles.insert(projectile);
                 }
                 Weapon::Cb => {
-                    projectile.weapon = Weapon::Cb;
                     for _ in 0..cvars.g_cluster_bomb_count {
-                        let speed = cvars.g_cluster_bomb_speed;
+                        let speed = stats.speed;
                         let spread_forward;
                         let spread_sideways;
                         if cvars.g_cluster_bomb_speed_spread_gaussian {
@@ -381,39 +389,19 @@ pub fn shooting(cvars: &Cvars, gs: &mut GameState) {
                         }
                         projectile.vel = Vec2f::new(speed + spread_forward, spread_sideways)
                             .rotated_z(shot_angle)
-                            + cvars.g_cluster_bomb_vehicle_velocity_factor * vehicle.vel;
+                            + stats.vehicle_velocity_factor * vehicle.vel;
                         projectile.explode_time = gs.game_time
                             + cvars.g_cluster_bomb_time
                             + gs.rng.sample(gs.range_uniform11) * cvars.g_cluster_bomb_time_spread;
                         gs.projectiles.insert(projectile.clone());
                     }
                 }
-                Weapon::Rockets => {
-                    projectile.weapon = Weapon::Rockets;
-                    projectile.vel = Vec2f::new(cvars.g_rockets_speed, 0.0).rotated_z(shot_angle)
-                        + cvars.g_rockets_vehicle_velocity_factor * vehicle.vel;
-                    gs.projectiles.insert(projectile);
-                }
-                Weapon::Hm => {
-                    projectile.weapon = Weapon::Hm;
-                    projectile.vel = Vec2f::new(cvars.g_homing_missile_speed_initial, 0.0)
-                        .rotated_z(shot_angle)
-                        + cvars.g_homing_missile_vehicle_velocity_factor * vehicle.vel;
-                    gs.projectiles.insert(projectile);
-                }
                 Weapon::Gm => {
-                    projectile.weapon = Weapon::Gm;
-                    projectile.vel = Vec2f::new(cvars.g_guided_missile_speed_initial, 0.0)
-                        .rotated_z(shot_angle)
-                        + cvars.g_guided_missile_vehicle_velocity_factor * vehicle.vel;
                     // TODO angle (maybe also HM)
                     let handle = gs.projectiles.insert(projectile);
                     player.guided_missile = Some(handle);
                 }
-                Weapon::Bfg => {
-                    projectile.weapon = Weapon::Bfg;
-                    projectile.vel = Vec2f::new(cvars.g_bfg_speed, 0.0).rotated_z(shot_angle)
-                        + cvars.g_bfg_vehicle_velocity_factor * vehicle.vel;
+                Weapon::Rail | Weapon::Rockets | Weapon::Hm | Weapon::Bfg => {
                     gs.projectiles.insert(projectile);
                 }
             }
@@ -508,7 +496,7 @@ pub fn projectiles(cvars: &Cvars, gs: &mut GameState, map: &Map) {
                 if cvars.d_tracing {
                     dbg_cross!(nearest_point, 0.5);
                 }
-                let dmg = cvars.g_weapon_damage_direct(projectile.weapon);
+                let dmg = cvars.g_weapon(projectile.weapon).damage_direct;
 
                 if is_rail {
                     gs.rail_hits.insert(proj_handle, vehicle_handle);
@@ -604,7 +592,7 @@ fn projectile_impact(cvars: &Cvars, gs: &mut GameState, projectile_handle: Index
     let owner = projectile.owner;
 
     // Vehicle explosion first so it's below projectile explosion because it looks better.
-    let expl_scale = cvars.g_weapon_explosion_scale(weapon);
+    let expl_scale = cvars.g_weapon(weapon).explosion_scale;
     if expl_scale > 0.0 {
         gs.explosions.push(Explosion::new(
             hit_pos,
@@ -614,8 +602,8 @@ fn projectile_impact(cvars: &Cvars, gs: &mut GameState, projectile_handle: Index
         ));
     }
 
-    let expl_damage = expl_scale * cvars.g_weapon_explosion_damage(weapon);
-    let expl_radius = expl_scale * cvars.g_weapon_explosion_radius(weapon);
+    let expl_damage = expl_scale * cvars.g_weapon(weapon).explosion_damage;
+    let expl_radius = expl_scale * cvars.g_weapon(weapon).explosion_radius;
     if expl_damage > 0.0 || expl_radius > 0.0 {
         explosion_damage(
             cvars,
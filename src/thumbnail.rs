@@ -0,0 +1,121 @@
+//! Render a map overview to an image on the CPU.
+//!
+//! Each tile is a block of pixels colored by its `Kind` so it works with any tileset
+//! and doesn't need a window or GPU - e.g. for a map picker, docs or checking generated maps in CI.
+
+use std::path::Path;
+
+use image::{ImageResult, Rgba, RgbaImage};
+
+use crate::map::{Kind, Map, Vec2u};
+
+/// Colors of team spawns from the map's metadata, teams past the end wrap around.
+const TEAM_COLORS: [[u8; 3]; 4] = [
+    [220, 40, 40],
+    [40, 90, 255],
+    [230, 230, 230],
+    [180, 60, 220],
+];
+
+fn kind_color(kind: Kind) -> [u8; 3] {
+    match kind {
+        Kind::Normal => [60, 120, 45],
+        Kind::Spawn => [255, 230, 0],
+        Kind::Wall => [85, 85, 85],
+        Kind::Water => [40, 80, 190],
+        Kind::Snow => [190, 200, 210],
+        Kind::Base => [255, 120, 0],
+    }
+}
+
+/// Render the map with `scale`×`scale` pixels per tile.
+///
+/// Spawns and bases are drawn in bright colors so they stand out,
+/// team spawns from the metadata use their team's color.
+/// With `scale` 3 and more, spawns and bases also get a dark dot in the middle.
+pub fn render_thumbnail(map: &Map, scale: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(map.width() as u32 * scale, map.height() as u32 * scale);
+
+    for r in 0..map.height() {
+        for c in 0..map.width() {
+            let index = Vec2u::new(c, r);
+            let color = kind_color(map.surface_at_index(index).kind);
+            fill_tile(&mut img, index, scale, color);
+        }
+    }
+    for team_spawn in &map.meta().team_spawns {
+        // Metadata isn't checked against the map when loading, see `MapIssue::MisplacedEntity`.
+        let index = team_spawn.index;
+        if index.x >= map.width() || index.y >= map.height() {
+            continue;
+        }
+        let color = TEAM_COLORS[team_spawn.team % TEAM_COLORS.len()];
+        fill_tile(&mut img, team_spawn.index, scale, color);
+    }
+
+    if scale >= 3 {
+        for &index in map.spawns().iter().chain(map.bases()) {
+            let center = index.as_::<u32>() * scale + scale / 2;
+            img.put_pixel(center.x, center.y, Rgba([0, 0, 0, 255]));
+        }
+    }
+
+    img
+}
+
+/// Render the map (see `render_thumbnail`) and save it, the format is determined by the extension.
+pub fn save_thumbnail(map: &Map, scale: u32, path: impl AsRef<Path>) -> ImageResult<()> {
+    render_thumbnail(map, scale).save(path)
+}
+
+fn fill_tile(img: &mut RgbaImage, index: Vec2u, scale: u32, color: [u8; 3]) {
+    let [r, g, b] = color;
+    let top_left = index.as_::<u32>() * scale;
+    for y in top_left.y..top_left.y + scale {
+        for x in top_left.x..top_left.x + scale {
+            img.put_pixel(x, y, Rgba([r, g, b, 255]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs, io::Cursor};
+
+    use image::ImageOutputFormat;
+
+    use crate::map::{self, MapMeta};
+
+    #[test]
+    fn test_thumbnail() {
+        let tex_list_text = fs::read_to_string("assets/texture_list.txt").unwrap();
+        let surfaces = map::load_tex_list(&tex_list_text).unwrap();
+        let mut map = map::load_map("36 0 60\n0 16 0\n60 0 36", surfaces).unwrap();
+        let meta =
+            map::load_meta("team_spawn 1 2 2\nteam_spawn 0 3 1", MapMeta::default()).unwrap();
+        map.set_meta(meta);
+
+        let img = render_thumbnail(&map, 1);
+        assert_eq!(img.dimensions(), (3, 3));
+        assert_eq!(img[(0, 0)], Rgba([255, 230, 0, 255]));
+        assert_eq!(img[(1, 0)], Rgba([60, 120, 45, 255]));
+        assert_eq!(img[(2, 0)], Rgba([255, 120, 0, 255]));
+        assert_eq!(img[(1, 1)], Rgba([85, 85, 85, 255]));
+        assert_eq!(img[(2, 2)], Rgba([40, 90, 255, 255]));
+
+        let img = render_thumbnail(&map, 4);
+        assert_eq!(img.dimensions(), (12, 12));
+        assert_eq!(img[(4, 4)], img[(7, 7)]);
+        assert_eq!(img[(1, 1)], Rgba([255, 230, 0, 255]));
+        assert_eq!(img[(2, 2)], Rgba([0, 0, 0, 255]));
+        assert_eq!(img[(10, 2)], Rgba([0, 0, 0, 255]));
+
+        let mut png = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded, img);
+    }
+}